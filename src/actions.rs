@@ -2,13 +2,13 @@ use ansi_term::Color::Blue;
 use ansi_term::Style;
 use std::collections::HashMap;
 use std::env::split_paths;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Read;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::exit;
-use std::process::Command;
 use std::process::Stdio;
+use std::process::{Child, Command};
 use zip::write::SimpleFileOptions;
 use zip::ZipArchive;
 
@@ -24,6 +24,9 @@ use crate::console::ProgressBar;
 use crate::console::{print_step, print_success, print_warn};
 use crate::files;
 use crate::files::get_file_tree;
+use crate::lint;
+use crate::lock;
+use crate::lua_parser;
 use crate::project_config;
 use crate::targets::Arch;
 use crate::targets::OS;
@@ -282,13 +285,6 @@ impl CommandRunner {
         }
     }
 
-    pub fn with_args(&self, args: Vec<impl Into<String>>) -> Self {
-        let mut new = self.clone();
-        new.add_args(args);
-
-        new
-    }
-
     pub fn unrequire(&mut self) -> &mut Self {
         self.required = false;
         self
@@ -444,9 +440,10 @@ impl CommandRunner {
         self
     }
 
-    pub fn run(&self) {
+    // Returns whether the command succeeded
+    pub fn run(&self) -> bool {
         if self.ignore || !self.check_exists() {
-            return;
+            return false;
         }
 
         let mut quiet = self.quiet;
@@ -480,7 +477,7 @@ impl CommandRunner {
                     exit_err(msg);
                 } else {
                     print_err(msg);
-                    return;
+                    return false;
                 }
             }
         };
@@ -514,7 +511,40 @@ impl CommandRunner {
             if self.required {
                 exit(1);
             }
+
+            return false;
         }
+
+        true
+    }
+
+    // Starts the command without waiting for it to finish
+    pub fn spawn(&self) -> Result<Child, String> {
+        self.start(false)
+    }
+
+    // Same as `spawn`, but stdout and stderr are read through the returned child
+    pub fn spawn_piped(&self) -> Result<Child, String> {
+        self.start(true)
+    }
+
+    fn start(&self, piped: bool) -> Result<Child, String> {
+        if self.ignore || !self.check_exists() {
+            return Err(format!("'{}' can't be run", self.command));
+        }
+
+        println!("{} {}", Self::get_exe_prefix(), self.to_string());
+
+        let mut command = Command::new(self.get_path().unwrap());
+        command.args(&self.args).envs(&self.env);
+
+        if piped {
+            command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        }
+
+        command
+            .spawn()
+            .map_err(|err| format!("Failed to execute: {}:\n {}", err, self.to_string()))
     }
 }
 
@@ -548,7 +578,9 @@ pub fn compile(arch: Arch, os: OS) {
 
         files::create_dir(&new_path.parent().unwrap());
 
-        let cmd = compiler.with_args(vec![
+        let mut cmd = compiler.clone();
+
+        cmd.add_args(vec![
             "-b".to_string(),
             script.display().to_string(),
             new_path.display().to_string(),
@@ -567,64 +599,69 @@ pub fn compile(arch: Arch, os: OS) {
     bar.finish();
 }
 
-pub fn get_parser() -> Option<CommandRunner> {
-    let parser = CommandRunner::new(config::get().software.luac);
-
-    if !parser.exists() {
-        return None;
+pub fn parse_all(root: impl Into<PathBuf>) {
+    if !try_parse_all(root) {
+        exit(1);
     }
-
-    Some(parser)
 }
 
-pub fn parse_all(root: impl Into<PathBuf>) {
+// Like `parse_all`, but reports every invalid script instead of exiting.
+// Returns whether all scripts are valid.
+pub fn try_parse_all(root: impl Into<PathBuf>) -> bool {
     let root: PathBuf = root.into();
-    let parser_res = get_parser();
-
-    if parser_res.is_none() {
-        print_warn(format!("luac Lua parser not found. Skipping."));
-        return;
-    }
+    let scripts = files::get_file_tree_of_type(&root, "lua");
+    let mut parsed: Vec<(String, String, lua_parser::Chunk)> = Vec::new();
+    let mut error_count = 0;
+    let mut invalid_count = 0;
 
-    let parser = parser_res.unwrap();
-    let scripts = files::get_file_tree_of_type(root, "lua");
+    // Same file names as `lover lint`, relative to the project
+    let project_dir = project_config::find_project_dir().unwrap_or(root);
 
     print_step("Checking validity of Lua scripts...");
 
     for script in &scripts {
-        parser
-            .with_args(vec!["-p", script.to_str().unwrap()])
-            .set_quiet(true)
-            .run();
-    }
+        let script_path_str = script.to_str().unwrap();
+        let code = fs::read_to_string(script)
+            .unwrap_or_else(|err| exit_err(format!("Failed to read {}: {}", script_path_str, err)));
 
-    print_step("Checking for deprecated features...");
+        let file = files::skip_path_string(script, &project_dir).replace('\\', "/");
+        let (chunk, errors) = lua_parser::parse(&code);
 
-    let env_repl = get_env_replacement_map();
+        for err in &errors {
+            print_err(format!(
+                "{}:{}:{}: {} [syntax]",
+                file, err.line, err.column, err.message
+            ));
+        }
 
-    for script in &scripts {
-        let mut file = files::open(&script);
-        let mut buf = String::new();
-        let script_path_str = script.to_str().unwrap();
+        error_count += errors.len();
+        invalid_count += (!errors.is_empty()) as usize;
+        parsed.push((file, code, chunk));
+    }
 
-        file.read_to_string(&mut buf).unwrap_or_else(|err| {
-            exit_err(format!(
-                "Failed to read buffer of {}: {}",
-                script_path_str, err
-            ))
-        });
+    if error_count != 0 {
+        print_err(format!(
+            "Found {} syntax errors in {} of {} scripts",
+            error_count,
+            invalid_count,
+            scripts.len()
+        ));
+        return false;
+    }
 
-        let lines: Vec<&str> = buf.lines().collect();
+    print_step("Checking for deprecated features...");
 
-        for i in 0..lines.len() {
-            let line = lines[i].to_string();
+    let env_repl = get_env_replacement_map();
 
+    for (file, code, _) in &parsed {
+        for (i, line) in code.lines().enumerate() {
             for (old, new) in &env_repl {
-                if line.contains(old) {
+                if let Some(column) = line.find(old.as_str()) {
                     print_warn(format!(
-                        "{}:{} Use '{}' instead of {}",
-                        script_path_str,
+                        "{}:{}:{}: Use '{}' instead of {}",
+                        file,
                         i + 1,
+                        column + 1,
                         new,
                         old
                     ));
@@ -633,7 +670,30 @@ pub fn parse_all(root: impl Into<PathBuf>) {
         }
     }
 
+    check_love_api(&parsed);
+
     print_success_verbose(&get_command_line_settings(), "Parsing successful");
+    true
+}
+
+// Reports the LOVE API issues `lover lint` would, for the already parsed scripts
+fn check_love_api(parsed: &[(String, String, lua_parser::Chunk)]) {
+    if project_config::find_project_dir().is_none() {
+        return;
+    }
+
+    let project = project_config::get();
+    let mut linter = lint::Linter::new(&project);
+    linter.set_checked_version(lock::get_checked_love_version());
+
+    let scripts: Vec<(String, &lua_parser::Block)> = parsed
+        .iter()
+        .map(|(file, _, chunk)| (file.clone(), &chunk.block))
+        .collect();
+
+    for issue in linter.lint_love_api(&scripts) {
+        print_warn(issue.to_string());
+    }
 }
 
 pub fn clean(path: &Path) {
@@ -690,6 +750,43 @@ pub fn add_to_archive(archive_path: &Path, file_path: &Path, inner_path: &Path)
     });
 }
 
+pub fn add_dir_to_archive(archive_path: &Path, dir: &Path, inner_dir: &Path) {
+    let archive_file = files::open_rw(archive_path);
+
+    let mut zip = zip::ZipWriter::new_append(archive_file).unwrap_or_else(|err| {
+        exit_err(format!("Failed to open zip: {}", err));
+    });
+
+    print_step_verbose(
+        &get_command_line_settings(),
+        format!(
+            "Adding {} to {}",
+            dir.to_str().unwrap(),
+            archive_path.to_str().unwrap()
+        ),
+    );
+
+    for path in get_file_tree(dir) {
+        let inner_path = inner_dir.join(files::skip_path(&path, dir));
+        let mut buf: Vec<u8> = Vec::new();
+
+        files::open(&path).read_to_end(&mut buf).unwrap();
+
+        zip.start_file_from_path(&inner_path, SimpleFileOptions::default())
+            .unwrap_or_else(|err| {
+                exit_err(format!(
+                    "Failed to start file '{}': {}",
+                    &inner_path.to_str().unwrap(),
+                    err
+                ))
+            });
+
+        zip.write_all(&buf).unwrap_or_else(|err| {
+            exit_err(format!("Failed to write to zip: {}", err));
+        });
+    }
+}
+
 pub fn append_file(from: &Path, to: &Path, text: impl Into<String>) {
     let mut from_file = files::open(from);
     let mut to_file = files::open_append(to);
@@ -151,3 +151,94 @@ pub fn replace_file_in_squashfs(
         .write(sfs_file)
         .unwrap_or_else(|err| exit_err(format!("Failed to save new SquashFS: {}", err)));
 }
+
+// Extracts the whole SquashFS into a directory, keeping permissions and symlinks
+pub fn extract_squashfs_dir(squashfs_path: &Path, output_path: &Path) {
+    let reader = read_squashfs(squashfs_path);
+
+    for node in reader.files() {
+        let inner_path = node.fullpath.strip_prefix("/").unwrap_or(&node.fullpath);
+        let path = output_path.join(inner_path);
+
+        match &node.inner {
+            InnerNode::Dir(_) => files::create_dir(&path),
+            InnerNode::File(f) => {
+                write_from_squashfs_file(&reader, f, &path);
+
+                #[cfg(target_family = "unix")]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+
+                    let perms = std::fs::Permissions::from_mode(node.header.permissions.into());
+                    std::fs::set_permissions(&path, perms).unwrap_or_else(|err| {
+                        exit_err(format!(
+                            "Failed to set permissions of '{}': {}",
+                            path.to_str().unwrap(),
+                            err
+                        ));
+                    });
+                }
+            }
+            #[cfg(target_family = "unix")]
+            InnerNode::Symlink(link) => {
+                std::os::unix::fs::symlink(&link.link, &path).unwrap_or_else(|err| {
+                    exit_err(format!(
+                        "Failed to create symlink '{}': {}",
+                        path.to_str().unwrap(),
+                        err
+                    ));
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backhand::NodeHeader;
+
+    #[test]
+    #[cfg(target_family = "unix")]
+    fn extract_squashfs_tree() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join("lover_test_squashfs");
+        let _ = std::fs::remove_dir_all(&dir);
+        files::create_dir(&dir);
+
+        let squashfs = dir.join("test.squashfs");
+        let out = dir.join("out");
+
+        let mut writer = FilesystemWriter::default();
+        writer
+            .push_dir("bin", NodeHeader::new(0o755, 0, 0, 0))
+            .unwrap();
+        writer
+            .push_file(
+                &b"#!/bin/sh"[..],
+                "bin/love",
+                NodeHeader::new(0o755, 0, 0, 0),
+            )
+            .unwrap();
+        writer
+            .push_symlink("bin/love", "AppRun", NodeHeader::new(0o777, 0, 0, 0))
+            .unwrap();
+        writer.write(files::create(&squashfs)).unwrap();
+
+        extract_squashfs_dir(&squashfs, &out);
+
+        let love = out.join("bin").join("love");
+        let mode = std::fs::metadata(&love).unwrap().permissions().mode();
+
+        assert_eq!(std::fs::read_to_string(&love).unwrap(), "#!/bin/sh");
+        assert_eq!(mode & 0o777, 0o755);
+        assert_eq!(
+            std::fs::read_link(out.join("AppRun")).unwrap(),
+            Path::new("bin/love")
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
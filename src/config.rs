@@ -1,6 +1,6 @@
-use crate::{actions::CommandRunner, console::exit_err};
+use crate::{actions::CommandRunner, console::exit_err, deps::CustomDependency};
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
 //const DKP_TOOLS: &str = "/opt/devkitpro/tools/bin/";
 
@@ -17,6 +17,12 @@ pub struct Config {
 
     #[serde(default = "Software::default")]
     pub software: Software,
+
+    #[serde(default = "Deps::default")]
+    pub deps: Deps,
+
+    #[serde(default)]
+    pub dependencies: HashMap<String, CustomDependency>,
 }
 
 impl Config {
@@ -26,6 +32,8 @@ impl Config {
             build: Build::default(),
             run: Run::default(),
             software: Software::default(),
+            deps: Deps::default(),
+            dependencies: HashMap::new(),
         }
     }
 
@@ -54,18 +62,52 @@ impl Config {
 #[derive(Deserialize)]
 pub struct Run {
     pub prime: bool,
+
+    #[serde(default = "Run::default_log")]
+    pub log: bool,
+
+    #[serde(default = "Run::default_max_logs")]
+    pub max_logs: usize,
+
+    // Address of the debugger server used by `lover run --debug`
+    #[serde(default = "Run::default_debug_host")]
+    pub debug_host: String,
+
+    #[serde(default = "Run::default_debug_port")]
+    pub debug_port: u16,
 }
 
 impl Run {
     pub fn default() -> Self {
         Self {
             prime: Run::default_prime(),
+            log: Run::default_log(),
+            max_logs: Run::default_max_logs(),
+            debug_host: Run::default_debug_host(),
+            debug_port: Run::default_debug_port(),
         }
     }
 
     fn default_prime() -> bool {
         false
     }
+
+    fn default_log() -> bool {
+        false
+    }
+
+    fn default_max_logs() -> usize {
+        20
+    }
+
+    fn default_debug_host() -> String {
+        "127.0.0.1".to_string()
+    }
+
+    // Default port of MobDebug
+    fn default_debug_port() -> u16 {
+        8172
+    }
 }
 
 #[derive(Deserialize)]
@@ -93,13 +135,62 @@ impl Build {
     }*/
 }
 
+#[derive(Deserialize)]
+pub struct Deps {
+    // Base URL or local directory used instead of GitHub.
+    // Releases are read from `<mirror>/repos/<owner>/<repo>/releases/<release>`
+    // and source archives from `<mirror>/<owner>/<repo>/archive/refs/heads/<branch>.zip`
+    pub mirror: Option<String>,
+
+    #[serde(default = "Deps::default_offline")]
+    pub offline: bool,
+
+    // Seconds, 0 disables the timeout
+    #[serde(default = "Deps::default_timeout")]
+    pub timeout: u64,
+
+    #[serde(default = "Deps::default_retries")]
+    pub retries: u32,
+
+    // Used if the GITHUB_TOKEN environment variable is not set
+    pub github_token: Option<String>,
+
+    // Overrides proxies from the environment
+    pub proxy: Option<String>,
+}
+
+impl Deps {
+    pub fn default() -> Self {
+        Self {
+            mirror: None,
+            offline: Self::default_offline(),
+            timeout: Self::default_timeout(),
+            retries: Self::default_retries(),
+            github_token: None,
+            proxy: None,
+        }
+    }
+
+    fn default_offline() -> bool {
+        false
+    }
+
+    fn default_timeout() -> u64 {
+        30
+    }
+
+    fn default_retries() -> u32 {
+        3
+    }
+}
+
 #[derive(Deserialize)]
 pub struct Software {
     #[serde(default = "Software::default_love")]
     pub love: String,
 
-    #[serde(default = "Software::default_luac")]
-    pub luac: String,
+    #[serde(default = "Software::default_luajit")]
+    pub luajit: String,
 
     #[serde(default = "Software::default_wine")]
     #[allow(dead_code)]
@@ -125,7 +216,7 @@ impl Software {
     pub fn default() -> Self {
         Software {
             love: Software::default_love(),
-            luac: Software::default_luac(),
+            luajit: Software::default_luajit(),
             wine: Software::default_wine(),
             rcedit: Software::default_rcedit(), /*
                                                 smdhtool: Software::default_smdhtool(),
@@ -142,8 +233,8 @@ impl Software {
         return "love".to_string();
     }
 
-    fn default_luac() -> String {
-        "luac".to_string()
+    fn default_luajit() -> String {
+        "luajit".to_string()
     }
 
     fn default_wine() -> String {
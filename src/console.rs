@@ -2,14 +2,21 @@ use ansi_term::Color::{Blue, Cyan, Green, Purple, Red, Yellow};
 use ansi_term::Style;
 use std::io::{stdin, stdout, BufRead, Read, Write};
 use std::process::exit;
+use std::time::{Duration, Instant};
 use termsize::Size;
 
 use crate::config;
 
+// Flags followed by a value, as in `--target win64`.
+// The value is optional, it's not taken if the flag is last or followed by another flag.
+// `--log` is not one, so it can be followed by the game's arguments. Its file is given as `--log=<file>`.
+pub const VALUE_FLAGS: &[&str] = &["target", "love", "instances", "runner", "junit"];
+
 pub struct CommandLineSettings {
     pub args: Vec<String>,
     pub flags: Vec<String>,
     pub verbose: bool,
+    pub offline: bool,
 }
 
 impl CommandLineSettings {
@@ -18,7 +25,19 @@ impl CommandLineSettings {
     }
 
     pub fn has_flag(&self, flag: &str) -> bool {
-        return self.flags.contains(&flag.to_string());
+        self.flags
+            .iter()
+            .any(|i| i == flag || i.starts_with(&format!("{}=", flag)))
+    }
+
+    // Value of `--name=value` or `--name value`
+    pub fn get_flag_value(&self, flag: &str) -> Option<String> {
+        let prefix = format!("{}=", flag);
+
+        self.flags
+            .iter()
+            .find_map(|i| i.strip_prefix(&prefix))
+            .map(|value| value.to_string())
     }
 }
 
@@ -78,6 +97,11 @@ impl ProgressBar {
     }
 
     pub fn update(&self, progress: usize) {
+        print!("\r{}", self.render(progress));
+        flush();
+    }
+
+    pub fn render(&self, progress: usize) -> String {
         let term_width = termsize::get().unwrap_or(Size { rows: 1, cols: 200 }).cols;
 
         let mut bar_margin: usize = (term_width as f32 * 0.3).max(42.0) as usize;
@@ -113,8 +137,7 @@ impl ProgressBar {
             format!("{prefix} {pre_space} [{fill}{spaces}] {disp_progress}/{disp_max} {suffix}");
         let clear_space = " ".repeat(term_width.saturating_sub(bar_string.len() as u16) as usize);
 
-        print!("\r{}{}", bar_string, clear_space);
-        flush();
+        format!("{}{}", bar_string, clear_space)
     }
 
     pub fn finish(&self) {
@@ -122,33 +145,100 @@ impl ProgressBar {
     }
 }
 
-pub fn get_command_line_settings() -> CommandLineSettings {
-    let conf = config::get();
+// Several progress bars drawn on separate lines, e.g. for parallel downloads
+pub struct MultiProgressBar {
+    pub bars: Vec<ProgressBar>,
+    progress: Vec<usize>,
+    drawn: bool,
+    last_draw: Option<Instant>,
+}
+
+impl MultiProgressBar {
+    pub fn new(bars: Vec<ProgressBar>) -> Self {
+        Self {
+            progress: vec![0; bars.len()],
+            bars,
+            drawn: false,
+            last_draw: None,
+        }
+    }
+
+    pub fn update(&mut self, index: usize, progress: usize) {
+        self.progress[index] = progress;
+
+        // Redrawing every line on each update would flood the terminal
+        let throttled = self
+            .last_draw
+            .is_some_and(|time| time.elapsed() < Duration::from_millis(100));
+
+        if !throttled {
+            self.draw();
+        }
+    }
+
+    pub fn draw(&mut self) {
+        if self.drawn {
+            print!("\x1b[{}A", self.bars.len());
+        }
+
+        for (bar, progress) in self.bars.iter().zip(&self.progress) {
+            println!("\r{}", bar.render(*progress));
+        }
+
+        flush();
+
+        self.drawn = true;
+        self.last_draw = Some(Instant::now());
+    }
 
+    pub fn finish(&mut self) {
+        self.draw();
+    }
+}
+
+// Splits arguments into positional arguments and flags without the `--` prefix.
+// Values of `VALUE_FLAGS` are stored as `name=value`.
+pub fn parse_args(input: impl IntoIterator<Item = String>) -> (Vec<String>, Vec<String>) {
     let mut args: Vec<String> = Vec::new();
     let mut flags: Vec<String> = Vec::new();
 
-    let mut first = false;
+    let mut input = input.into_iter().peekable();
 
-    for i in std::env::args() {
-        if !first {
-            first = true;
-            continue;
-        }
+    while let Some(i) = input.next() {
+        let flag = match i.strip_prefix("--") {
+            Some(flag) => flag,
+            None => {
+                args.push(i);
+                continue;
+            }
+        };
 
-        if i.starts_with("--") {
-            flags.push(i.replace("--", ""));
-        } else {
-            args.push(i);
+        if VALUE_FLAGS.contains(&flag) {
+            if let Some(value) = input.next_if(|next| !next.starts_with("--")) {
+                flags.push(format!("{}={}", flag, value));
+                continue;
+            }
         }
+
+        flags.push(flag.to_string());
     }
 
+    (args, flags)
+}
+
+pub fn get_command_line_settings() -> CommandLineSettings {
+    let conf = config::get();
+
+    let (args, flags) = parse_args(std::env::args().skip(1));
+
     let verbose_flag = (&flags).contains(&"verbose".to_string());
+    let offline_flag = flags.contains(&"offline".to_string());
 
     CommandLineSettings {
         args: args,
         flags: flags,
         verbose: conf.verbose_logging || verbose_flag,
+        offline: conf.deps.offline || offline_flag,
     }
 }
 
@@ -269,3 +359,37 @@ pub fn get_step_prefix() -> String {
 pub fn print_step(message: impl Into<String>) {
     println!("{} {}", get_step_prefix(), message.into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_values() {
+        let input = ["run", "--target", "win64", "--watch", "--level=2", "extra"];
+        let (args, flags) = parse_args(input.map(String::from));
+
+        assert_eq!(args, vec!["run", "extra"]);
+        assert_eq!(flags, vec!["target=win64", "watch", "level=2"]);
+
+        let settings = CommandLineSettings {
+            args,
+            flags,
+            verbose: false,
+            offline: false,
+        };
+
+        assert!(settings.has_flag("target"));
+        assert!(settings.has_flag("level"));
+        assert!(!settings.has_flag("tar"));
+        assert_eq!(settings.get_flag_value("target").unwrap(), "win64");
+        assert!(settings.get_flag_value("watch").is_none());
+
+        let (args, flags) = parse_args(["run", "--log", "mylevel"].map(String::from));
+        assert_eq!(args, vec!["run", "mylevel"]);
+        assert_eq!(flags, vec!["log"]);
+
+        let (_, flags) = parse_args(["run", "--log=game.txt", "--watch"].map(String::from));
+        assert_eq!(flags, vec!["log=game.txt", "watch"]);
+    }
+}
@@ -1,14 +1,26 @@
 use regex::Regex;
-use serde::Deserialize;
-use std::{fs, path::PathBuf, process::exit};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    process::exit,
+    sync::{Mutex, OnceLock},
+    thread,
+    time::Duration,
+};
 
 use crate::{
     config,
     console::{
         self, confirm_or_exit, exit_err, print_err, print_note, print_step, print_success,
-        print_warn, ProgressBar,
+        print_warn, MultiProgressBar, ProgressBar,
     },
+    files,
     http::{self, Downloadable},
+    lock::{self, Lockfile},
+    lua_deps,
+    project_config::{self, KnownProjects, ProjectConfig},
 };
 
 pub enum RepoDownload<'a> {
@@ -48,13 +60,125 @@ impl GitHubRelease {
 pub struct GithubReleaseAsset {
     pub browser_download_url: String,
     pub name: String,
-    //pub digest: String // sha256
+    pub digest: Option<String>, // "sha256:...", missing in older releases
 }
 
 impl GithubReleaseAsset {
     pub fn matches_pattern(&self, regex: Regex) -> bool {
         regex.is_match(&self.name)
     }
+
+    pub fn get_sha256(&self) -> Option<String> {
+        self.digest
+            .as_ref()
+            .and_then(|digest| digest.strip_prefix("sha256:"))
+            .map(|hash| hash.to_lowercase())
+    }
+}
+
+// Exact release of a dependency
+pub struct ResolvedDependency {
+    pub tag: String,
+    pub url: String,
+    pub sha256: Option<String>,
+}
+
+impl ResolvedDependency {
+    pub fn get_asset_name(&self) -> String {
+        self.url.rsplit('/').next().unwrap_or(&self.url).to_string()
+    }
+
+    pub fn request(&self) -> Downloadable {
+        let mut download = Downloadable::request(&self.url);
+
+        download.set_sha256(self.sha256.to_owned());
+        download
+    }
+}
+
+// Dependency declared in `[dependencies.<name>]` of lover.toml or Lover's config.
+// Overrides a built-in dependency with the same name.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CustomDependency {
+    #[serde(default = "CustomDependency::default_description")]
+    pub description: String,
+    pub repo_owner: String,
+    pub repo: String,
+    pub asset: Option<String>,  // pattern of the release asset
+    pub branch: Option<String>, // downloads the source code instead
+    pub file_name: String,
+}
+
+impl CustomDependency {
+    fn default_description() -> String {
+        "Custom dependency".to_string()
+    }
+
+    pub fn validate(&self, name: &str) -> Result<(), String> {
+        let mode_err = match (&self.asset, &self.branch) {
+            (Some(_), Some(_)) => Some("'asset' and 'branch' can't be used together"),
+            (None, None) => Some("either 'asset' or 'branch' is required"),
+            _ => None,
+        };
+
+        if let Some(err) = mode_err {
+            return Err(format!("Dependency '{}': {}", name, err));
+        }
+
+        if let Some(pattern) = &self.asset {
+            Regex::new(pattern)
+                .map_err(|err| format!("Dependency '{}': invalid asset pattern: {}", name, err))?;
+        }
+
+        if self.file_name.is_empty() || self.file_name.contains(['/', '\\']) {
+            return Err(format!(
+                "Dependency '{}': 'file_name' must be a plain file name",
+                name
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn to_dependency<'a>(&'a self, name: &'a str) -> Dependency<'a> {
+        let mode = match &self.asset {
+            Some(pattern) => RepoDownload::LatestRelease(pattern),
+            None => RepoDownload::Source(self.branch.as_deref().unwrap_or_default()),
+        };
+
+        Dependency {
+            name,
+            description: &self.description,
+            file_name: &self.file_name,
+            mode,
+            repo: &self.repo,
+            repo_owner: &self.repo_owner,
+            version: None,
+        }
+    }
+}
+
+static CUSTOM_DEPS: OnceLock<Vec<(String, CustomDependency)>> = OnceLock::new();
+
+// Dependencies from Lover's config and the current project, loaded once.
+// Project dependencies take priority.
+pub fn get_custom_deps() -> &'static Vec<(String, CustomDependency)> {
+    CUSTOM_DEPS.get_or_init(|| {
+        let mut custom: HashMap<String, CustomDependency> = config::get().dependencies;
+
+        if let Some(path) = project_config::find_project_config() {
+            custom.extend(ProjectConfig::parse_file(path).dependencies);
+        }
+
+        let mut res: Vec<(String, CustomDependency)> = custom.into_iter().collect();
+        res.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (name, dep) in &res {
+            dep.validate(name).unwrap_or_else(|err| exit_err(err));
+        }
+
+        res
+    })
 }
 
 pub struct Dependency<'a> {
@@ -64,11 +188,50 @@ pub struct Dependency<'a> {
     pub mode: RepoDownload<'a>,
     pub repo: &'a str,
     pub repo_owner: &'a str,
+    pub version: Option<String>, // release tag, latest if `None`
 }
 
 impl<'a> Dependency<'a> {
+    pub fn get_file_name(&self) -> String {
+        match &self.version {
+            Some(version) => {
+                let path = Path::new(self.file_name);
+                let stem = path.file_stem().unwrap().to_str().unwrap();
+
+                match path.extension() {
+                    Some(ext) => format!("{}-{}.{}", stem, version, ext.to_str().unwrap()),
+                    None => format!("{}-{}", stem, version),
+                }
+            }
+            None => self.file_name.to_string(),
+        }
+    }
+
     pub fn get_path(&self) -> PathBuf {
-        get_dir().join(self.file_name)
+        get_dir().join(self.get_file_name())
+    }
+
+    pub fn get_display_name(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{}@{}", self.name, version),
+            None => self.name.to_string(),
+        }
+    }
+
+    pub fn is_love(&self) -> bool {
+        self.repo_owner == "love2d" && self.repo == "love"
+    }
+
+    pub fn set_version(&mut self, version: impl Into<String>) -> &mut Self {
+        if let RepoDownload::Source(_) = self.mode {
+            exit_err(format!(
+                "'{}' is downloaded from source and cannot be pinned to a version",
+                self.name
+            ));
+        }
+
+        self.version = Some(version.into());
+        self
     }
 
     pub fn is_installed(&self) -> bool {
@@ -95,18 +258,206 @@ impl<'a> Dependency<'a> {
         }
     }
 
-    pub fn fetch_download_url(&self) -> String {
+    pub fn resolve(&self) -> ResolvedDependency {
         match &self.get_instance() {
-            DependencyInstance::LatestRelease(dep) => dep.fetch_asset().browser_download_url,
-            DependencyInstance::Source(dep) => dep.get_download_url(),
+            DependencyInstance::LatestRelease(dep) => {
+                let release = dep.fetch_release();
+                let asset = dep.get_asset_from_release(&release);
+
+                ResolvedDependency {
+                    tag: release.tag_name,
+                    url: resolve_mirror_url(&asset.browser_download_url),
+                    sha256: asset.get_sha256(),
+                }
+            }
+            DependencyInstance::Source(dep) => ResolvedDependency {
+                tag: dep.branch.to_string(),
+                url: dep.get_download_url(),
+                sha256: None,
+            },
         }
     }
 
-    pub fn fetch_downloadable(&self) -> Downloadable {
-        Downloadable::request(self.fetch_download_url())
+    // Checks the installed file against the size and checksum recorded during installation.
+    // Hashing is skipped if `check_hash` is false.
+    pub fn verify(&self, check_hash: bool) -> Integrity {
+        let path = self.get_path();
+        let manifest = InstalledManifest::get();
+
+        let info = match manifest.get_file(&self.get_file_name()) {
+            Some(info) => info,
+            None => return Integrity::Unverified,
+        };
+
+        let size = match fs::metadata(&path) {
+            Ok(meta) => meta.len(),
+            Err(err) => {
+                return Integrity::Corrupted(format!(
+                    "Failed to read '{}': {}",
+                    path.to_str().unwrap(),
+                    err
+                ))
+            }
+        };
+
+        if size != info.size {
+            return Integrity::Corrupted(format!(
+                "Size of '{}' is {} bytes, expected {}",
+                self.get_display_name(),
+                size,
+                info.size
+            ));
+        }
+
+        if check_hash && !info.sha256.eq_ignore_ascii_case(&self.get_hash()) {
+            return Integrity::Corrupted(format!(
+                "Checksum of '{}' doesn't match",
+                self.get_display_name()
+            ));
+        }
+
+        Integrity::Valid
+    }
+
+    pub fn uninstall(&self) -> Result<(), String> {
+        let path = self.get_path();
+
+        fs::remove_file(&path)
+            .map_err(|err| format!("Failed to delete '{}': {}", path.to_str().unwrap(), err))?;
+
+        let mut manifest = InstalledManifest::get();
+        manifest.remove(&self.get_file_name());
+        manifest.save();
+
+        Ok(())
+    }
+
+    // Version of `file_name` if it's a versioned file of this dependency
+    pub fn get_file_version(&self, file_name: &str) -> Option<String> {
+        let path = Path::new(self.file_name);
+        let stem = path.file_stem()?.to_str()?;
+        let rest = file_name.strip_prefix(stem)?.strip_prefix('-')?;
+
+        let version = match path.extension() {
+            Some(ext) => rest.strip_suffix(&format!(".{}", ext.to_str()?))?,
+            None => rest,
+        };
+
+        match version.is_empty() {
+            true => None,
+            false => Some(version.to_string()),
+        }
+    }
+
+    // Installed files of this dependency, including pinned versions
+    pub fn get_installed_versions(&self) -> Vec<InstalledVersion> {
+        let manifest = InstalledManifest::get();
+        let mut res = Vec::new();
+
+        let entries = match fs::read_dir(get_dir()) {
+            Ok(entries) => entries,
+            Err(_) => return res,
+        };
+
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let size = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+
+            let version = if file_name == self.file_name {
+                manifest
+                    .get_file(&file_name)
+                    .and_then(|file| file.tag.to_owned())
+                    .unwrap_or("unknown".to_string())
+            } else {
+                match self.get_file_version(&file_name) {
+                    Some(version) => version,
+                    None => continue,
+                }
+            };
+
+            res.push(InstalledVersion {
+                pinned: file_name != self.file_name,
+                file_name,
+                version,
+                size,
+            });
+        }
+
+        res.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+        res
+    }
+
+    // Release the installed file came from, as recorded during installation.
+    // `None` if it's not installed or was installed by an older Lover version.
+    pub fn get_installed_release(&self) -> Option<ResolvedDependency> {
+        if !self.is_installed() {
+            return None;
+        }
+
+        let file = InstalledManifest::get()
+            .get_file(&self.get_file_name())?
+            .clone();
+
+        let tag = file.tag?;
+
+        let url = match (file.url, &self.mode) {
+            (Some(url), _) => url,
+            (None, RepoDownload::LatestRelease(_)) => format!(
+                "{}/releases/download/{}/{}",
+                self.get_repo_url(),
+                tag,
+                file.asset?
+            ),
+            (None, RepoDownload::Source(_)) => return None,
+        };
+
+        Some(ResolvedDependency {
+            tag,
+            url,
+            sha256: Some(file.sha256),
+        })
+    }
+
+    // Release tag recorded during installation
+    pub fn get_installed_tag(&self) -> Option<String> {
+        InstalledManifest::get()
+            .get_file(&self.get_file_name())
+            .and_then(|file| file.tag.to_owned())
+    }
+
+    // `None` for dependencies downloaded from source or pinned to a version
+    pub fn fetch_latest_tag(&self) -> Option<String> {
+        if self.version.is_some() {
+            return None;
+        }
+
+        match self.mode {
+            RepoDownload::LatestRelease(_) => {
+                Some(fetch_gh_latest_release(self.repo_owner, self.repo).tag_name)
+            }
+            RepoDownload::Source(_) => None,
+        }
+    }
+
+    pub fn get_hash(&self) -> String {
+        let path = self.get_path();
+
+        sha256::try_digest(&path).unwrap_or_else(|err| {
+            exit_err(format!(
+                "Failed to calculate checksum of '{}': {}",
+                path.to_str().unwrap(),
+                err
+            ));
+        })
     }
 }
 
+pub enum Integrity {
+    Valid,
+    Unverified, // installed by an older Lover version, without a recorded size and checksum
+    Corrupted(String),
+}
+
 pub struct ReleaseDependency<'a> {
     pub base: &'a Dependency<'a>,
     pub pattern: &'a str,
@@ -114,7 +465,10 @@ pub struct ReleaseDependency<'a> {
 
 impl<'a> ReleaseDependency<'a> {
     pub fn fetch_release(&self) -> GitHubRelease {
-        fetch_gh_latest_release(&self.base.repo_owner, &self.base.repo)
+        match &self.base.version {
+            Some(tag) => fetch_gh_tagged_release(self.base.repo_owner, self.base.repo, tag),
+            None => fetch_gh_latest_release(self.base.repo_owner, self.base.repo),
+        }
     }
 
     pub fn get_asset_from_release(&self, release: &GitHubRelease) -> GithubReleaseAsset {
@@ -129,10 +483,6 @@ impl<'a> ReleaseDependency<'a> {
 
         asset_res.unwrap().clone()
     }
-
-    pub fn fetch_asset(&self) -> GithubReleaseAsset {
-        self.get_asset_from_release(&self.fetch_release())
-    }
 }
 
 pub struct SourceDependency<'a> {
@@ -143,13 +493,31 @@ pub struct SourceDependency<'a> {
 impl<'a> SourceDependency<'a> {
     pub fn get_download_url(&self) -> String {
         format!(
-            "https://github.com/{}/{}/archive/refs/heads/{}.zip",
-            self.base.repo_owner, self.base.repo, self.branch
+            "{}/{}/{}/archive/refs/heads/{}.zip",
+            get_archive_url(),
+            self.base.repo_owner,
+            self.base.repo,
+            self.branch
         )
     }
 }
 
 pub fn get_deps<'a>() -> Vec<Dependency<'a>> {
+    let mut deps = get_builtin_deps();
+
+    for (name, custom) in get_custom_deps() {
+        let dep = custom.to_dependency(name);
+
+        match deps.iter().position(|d| d.name == name) {
+            Some(i) => deps[i] = dep,
+            None => deps.push(dep),
+        }
+    }
+
+    deps
+}
+
+pub fn get_builtin_deps<'a>() -> Vec<Dependency<'a>> {
     vec![
         // PC deps
         Dependency {
@@ -159,6 +527,7 @@ pub fn get_deps<'a>() -> Vec<Dependency<'a>> {
             mode: RepoDownload::LatestRelease(".*x86_64.AppImage"),
             repo: "love",
             repo_owner: "love2d",
+            version: None,
         },
         Dependency {
             name: "love-win32",
@@ -167,6 +536,7 @@ pub fn get_deps<'a>() -> Vec<Dependency<'a>> {
             mode: RepoDownload::LatestRelease(".*win32.zip"),
             repo: "love",
             repo_owner: "love2d",
+            version: None,
         },
         Dependency {
             name: "love-win64",
@@ -175,6 +545,7 @@ pub fn get_deps<'a>() -> Vec<Dependency<'a>> {
             mode: RepoDownload::LatestRelease(".*win64.zip"),
             repo: "love",
             repo_owner: "love2d",
+            version: None,
         },
         Dependency {
             name: "rcedit",
@@ -183,6 +554,7 @@ pub fn get_deps<'a>() -> Vec<Dependency<'a>> {
             mode: RepoDownload::LatestRelease(".*rcedit-x86.exe"),
             repo: "rcedit",
             repo_owner: "electron",
+            version: None,
         },
         // Console deps
         Dependency {
@@ -192,6 +564,7 @@ pub fn get_deps<'a>() -> Vec<Dependency<'a>> {
             mode: RepoDownload::LatestRelease(r"Nintendo\.3DS.*.zip"),
             repo: "lovepotion",
             repo_owner: "lovebrew",
+            version: None,
         },
         Dependency {
             name: "lovepotion-assets",
@@ -200,6 +573,7 @@ pub fn get_deps<'a>() -> Vec<Dependency<'a>> {
             mode: RepoDownload::LatestRelease("resources.zip"),
             repo: "bundler",
             repo_owner: "lovebrew",
+            version: None,
         },
         Dependency {
             name: "nest",
@@ -208,15 +582,36 @@ pub fn get_deps<'a>() -> Vec<Dependency<'a>> {
             mode: RepoDownload::Source("master"),
             repo: "nest",
             repo_owner: "lovebrew",
+            version: None,
         },
     ]
 }
 
+// Accepts `name` or `name@version`.
+// LOVE dependencies without a version follow `love_version` of the current project.
 pub fn get_dep<'a>(name: impl Into<String>) -> Option<Dependency<'a>> {
-    let name_str = name.into();
+    let name_str: String = name.into();
 
-    for dep in get_deps() {
+    let (name_str, version) = match name_str.split_once('@') {
+        Some((name, version)) => (name.to_string(), Some(version.to_string())),
+        None => (name_str, None),
+    };
+
+    for mut dep in get_deps() {
         if dep.name.to_lowercase() == name_str.to_owned().to_lowercase() {
+            match version {
+                Some(version) => {
+                    dep.set_version(version);
+                }
+                None => {
+                    if dep.is_love() {
+                        if let Some(version) = project_config::get_love_version() {
+                            dep.set_version(version);
+                        }
+                    }
+                }
+            }
+
             return Some(dep);
         }
     }
@@ -256,7 +651,21 @@ pub fn get_deps_by_strings<'a>(names: Vec<impl Into<String>>) -> Vec<Dependency<
 }
 
 pub fn install(names: Vec<String>) {
-    let deps = get_deps_by_strings(names);
+    install_deps(get_deps_by_strings(names), None);
+}
+
+// Dependencies present in the lockfile are downloaded from the locked URL and verified.
+pub fn install_deps(deps: Vec<Dependency>, lockfile: Option<&Lockfile>) {
+    if !can_download() {
+        let names: Vec<String> = deps.iter().map(|dep| dep.get_display_name()).collect();
+
+        exit_err(format!(
+            "The following dependencies are not installed and can't be downloaded in offline mode: {}",
+            names.join(", ")
+        ));
+    }
+
+    let mut resolved: Vec<ResolvedDependency> = Vec::new();
     let mut downloads: Vec<Downloadable> = Vec::new();
 
     let mut fetch_bar = ProgressBar::new(deps.len());
@@ -270,7 +679,19 @@ pub fn install(names: Vec<String>) {
     fetch_bar.update(fetch_progress);
 
     for dep in &deps {
-        downloads.push(dep.fetch_downloadable());
+        let locked = lockfile.and_then(|lock| lock.get(dep.name, dep.version.as_deref()));
+
+        let res = match locked {
+            Some(locked) => ResolvedDependency {
+                tag: locked.tag.to_owned(),
+                url: locked.url.to_owned(),
+                sha256: Some(locked.sha256.to_owned()),
+            },
+            None => dep.resolve(),
+        };
+
+        downloads.push(res.request());
+        resolved.push(res);
 
         fetch_progress += 1;
         fetch_bar.update(fetch_progress);
@@ -304,7 +725,7 @@ pub fn install(names: Vec<String>) {
             }
         };
 
-        println!("  {}: {} MB {}", dep.name, len_text, re);
+        println!("  {}: {} MB {}", dep.get_display_name(), len_text, re);
     }
 
     println!("\nTotal size: {} MB", total as f32 / (1024 * 1024) as f32);
@@ -319,11 +740,80 @@ pub fn install(names: Vec<String>) {
 
     print_step("Installing...");
 
-    for i in 0..downloads.len() {
-        let dep = deps.get(i).unwrap();
-        let download = downloads.get_mut(i).unwrap();
+    let mut bars: Vec<ProgressBar> = Vec::new();
+
+    for (dep, download) in deps.iter().zip(&downloads) {
+        let mut bar = ProgressBar::new(download.len().unwrap_or(0) as usize);
+        bar.set_prefix(format!("  {}", dep.get_display_name()));
+        bar.memory_mode();
+
+        bars.push(bar);
+    }
+
+    let multi_bar = Mutex::new(MultiProgressBar::new(bars));
+    let mut results: Vec<Result<String, String>> = Vec::new();
+
+    thread::scope(|scope| {
+        let mut handles = Vec::new();
 
-        download.download(&dep.get_path(), format!("  {}", dep.name));
+        for (i, (dep, download)) in deps.iter().zip(downloads.iter_mut()).enumerate() {
+            let multi_bar = &multi_bar;
+
+            handles.push(scope.spawn(move || {
+                download.download(&dep.get_path(), |progress| {
+                    let mut multi_bar = multi_bar.lock().unwrap();
+                    let max = multi_bar.bars[i].max;
+
+                    multi_bar.update(i, progress.min(max));
+                })
+            }));
+        }
+
+        for handle in handles {
+            results.push(
+                handle
+                    .join()
+                    .unwrap_or(Err("Download thread panicked".to_string())),
+            );
+        }
+    });
+
+    multi_bar.lock().unwrap().finish();
+
+    let mut manifest = InstalledManifest::get();
+    let mut failed = false;
+
+    for ((dep, res), resolved) in deps.iter().zip(results).zip(resolved) {
+        match res {
+            Ok(hash) => {
+                let size = fs::metadata(dep.get_path())
+                    .map(|meta| meta.len())
+                    .unwrap_or(0);
+
+                manifest.set(InstalledFile {
+                    name: dep.get_file_name(),
+                    size,
+                    sha256: hash,
+                    tag: Some(resolved.tag.to_owned()),
+                    asset: Some(resolved.get_asset_name()),
+                    url: Some(resolved.url.to_owned()),
+                });
+            }
+            Err(err) => {
+                print_err(format!(
+                    "Failed to install '{}': {}",
+                    dep.get_display_name(),
+                    err
+                ));
+                failed = true;
+            }
+        }
+    }
+
+    manifest.save();
+
+    if failed {
+        exit(1);
     }
 
     print_success("All dependencies successfully installed.");
@@ -333,6 +823,340 @@ pub fn install(names: Vec<String>) {
     ));
 }
 
+// Record of files installed in the dependency directory, used to detect corruption
+#[derive(Serialize, Deserialize)]
+pub struct InstalledManifest {
+    #[serde(default)]
+    #[serde(rename = "file")]
+    pub files: Vec<InstalledFile>,
+}
+
+impl InstalledManifest {
+    pub fn new() -> Self {
+        Self { files: Vec::new() }
+    }
+
+    pub fn get_path() -> PathBuf {
+        get_dir().join("installed.toml")
+    }
+
+    pub fn get() -> Self {
+        let path = Self::get_path();
+
+        if !path.exists() {
+            return Self::new();
+        }
+
+        let parse_res = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| toml::from_str(&text).map_err(|err| err.to_string()));
+
+        parse_res.unwrap_or_else(|err| {
+            print_warn(format!(
+                "Failed to read '{}': {}. Assuming it's empty.",
+                path.to_str().unwrap(),
+                err
+            ));
+            Self::new()
+        })
+    }
+
+    pub fn get_file(&self, name: &str) -> Option<&InstalledFile> {
+        self.files.iter().find(|file| file.name == name)
+    }
+
+    pub fn set(&mut self, file: InstalledFile) {
+        self.remove(&file.name);
+        self.files.push(file);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.files.retain(|file| file.name != name);
+    }
+
+    pub fn save(&self) {
+        let text = toml::to_string_pretty(&self).unwrap_or_else(|err| {
+            exit_err(format!(
+                "Failed to serialize installed files: {}. Please report a bug.",
+                err
+            ));
+        });
+
+        let _ = fs::write(Self::get_path(), text).map_err(|err| {
+            print_warn(format!("Failed to save installed files: {}", err));
+        });
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct InstalledFile {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+    pub tag: Option<String>, // missing for files installed by older Lover versions
+    pub asset: Option<String>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+pub struct InstalledVersion {
+    pub file_name: String,
+    pub version: String,
+    pub pinned: bool,
+    pub size: u64,
+}
+
+pub fn to_mb(bytes: u64) -> f32 {
+    bytes as f32 / (1024 * 1024) as f32
+}
+
+// Files in the dependency directory that are still needed, relative to it.
+// Pinned versions and Lua dependencies are kept only if a known project uses them.
+pub fn get_referenced_files() -> HashSet<PathBuf> {
+    let mut res: HashSet<PathBuf> = HashSet::new();
+    res.insert(PathBuf::from("installed.toml"));
+
+    for dep in get_deps() {
+        res.insert(PathBuf::from(dep.get_file_name()));
+    }
+
+    for (dir, project) in KnownProjects::get().get_configs() {
+        let lockfile = fs::read_to_string(dir.join(lock::LOCK_FILE))
+            .ok()
+            .and_then(|text| Lockfile::parse_str(&text).ok())
+            .unwrap_or_else(Lockfile::new);
+
+        res.extend(get_project_files(&project, lockfile));
+    }
+
+    res
+}
+
+// Dependency files used by a project, with its custom dependencies and locked versions
+fn get_project_files(project: &ProjectConfig, lockfile: Lockfile) -> HashSet<PathBuf> {
+    let mut res: HashSet<PathBuf> = HashSet::new();
+
+    // Custom dependencies of other projects are not in `get_deps()`
+    let custom = project
+        .dependencies
+        .iter()
+        .map(|(name, custom)| custom.to_dependency(name));
+
+    for mut dep in get_deps().into_iter().chain(custom) {
+        res.insert(PathBuf::from(dep.get_file_name()));
+
+        let love_version = project
+            .package
+            .love_version
+            .as_ref()
+            .filter(|_| dep.is_love());
+
+        let locked_versions = lockfile
+            .dependencies
+            .iter()
+            .filter(|locked| locked.name == dep.name)
+            .filter_map(|locked| locked.version.as_ref());
+
+        for version in love_version.into_iter().chain(locked_versions) {
+            dep.version = Some(version.to_owned());
+            res.insert(PathBuf::from(dep.get_file_name()));
+        }
+    }
+
+    for locked in lockfile.lua_dependencies {
+        if let Some(dep) = project.lua_dependencies.get(&locked.name) {
+            let archive = lua_deps::get_archive_path(
+                dep,
+                &ResolvedDependency {
+                    tag: locked.tag,
+                    url: locked.url,
+                    sha256: None,
+                },
+            );
+
+            res.insert(files::skip_path(archive, get_dir()));
+        }
+    }
+
+    res
+}
+
+// `build/temp` directories of known projects that weren't touched for a week
+pub fn get_stale_temp_dirs() -> Vec<PathBuf> {
+    let max_age = Duration::from_secs(60 * 60 * 24 * 7);
+
+    KnownProjects::get()
+        .get_configs()
+        .into_iter()
+        .map(|(dir, project)| project.paths.get_temp_dir_at(&dir))
+        .filter(|temp| {
+            if !temp.is_dir() {
+                return false;
+            }
+
+            let modified = files::get_file_tree(temp)
+                .iter()
+                .filter_map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+                .max();
+
+            match modified.and_then(|time| time.elapsed().ok()) {
+                Some(age) => age > max_age,
+                None => true,
+            }
+        })
+        .collect()
+}
+
+pub fn prune() {
+    print_step("Looking for unused files...");
+
+    let dir = get_dir();
+    let referenced = get_referenced_files();
+
+    let mut unused: Vec<PathBuf> = Vec::new();
+
+    if dir.exists() {
+        for path in files::get_file_tree(&dir) {
+            if !referenced.contains(&files::skip_path(&path, &dir)) {
+                unused.push(path);
+            }
+        }
+    }
+
+    let stale = get_stale_temp_dirs();
+
+    if unused.is_empty() && stale.is_empty() {
+        print_success("Nothing to prune.");
+        return;
+    }
+
+    let mut total: u64 = 0;
+
+    print_step("The following files will be deleted:");
+
+    for path in &unused {
+        let size = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+        total += size;
+
+        println!("  {}: {:.2} MB", path.to_str().unwrap(), to_mb(size));
+    }
+
+    for temp in &stale {
+        let size: u64 = files::get_file_tree(temp)
+            .iter()
+            .filter_map(|path| fs::metadata(path).ok())
+            .map(|meta| meta.len())
+            .sum();
+        total += size;
+
+        println!(
+            "  {} (stale): {:.2} MB",
+            temp.to_str().unwrap(),
+            to_mb(size)
+        );
+    }
+
+    println!("\nTotal: {:.2} MB", to_mb(total));
+    confirm_or_exit("Proceed with the removal?");
+
+    let mut manifest = InstalledManifest::get();
+    let mut failed = false;
+
+    let results = unused
+        .iter()
+        .map(|path| fs::remove_file(path).map(|_| path))
+        .chain(
+            stale
+                .iter()
+                .map(|temp| fs::remove_dir_all(temp).map(|_| temp)),
+        );
+
+    for res in results {
+        match res {
+            Ok(path) => manifest.remove(&files::skip_path_string(path, &dir)),
+            Err(err) => {
+                print_err(format!("Failed to delete: {}", err));
+                failed = true;
+            }
+        }
+    }
+
+    manifest.save();
+
+    if failed {
+        exit(1);
+    }
+
+    print_success(format!("Freed {:.2} MB", to_mb(total)));
+}
+
+pub struct OutdatedDependency<'a> {
+    pub dep: Dependency<'a>,
+    pub installed: Option<String>,
+    pub latest: String,
+}
+
+// Compares the installed tags of the given dependencies with their latest releases.
+// Dependencies that aren't installed, are pinned to a version or are downloaded from source are skipped.
+pub fn get_outdated(deps: Vec<Dependency>) -> Vec<OutdatedDependency> {
+    if !can_download() {
+        exit_err("Can't check for updates in offline mode.");
+    }
+
+    let mut res = Vec::new();
+
+    for dep in deps {
+        if !dep.is_installed() {
+            continue;
+        }
+
+        let latest = match dep.fetch_latest_tag() {
+            Some(tag) => tag,
+            None => continue,
+        };
+
+        let installed = dep.get_installed_tag();
+
+        if installed.as_ref() != Some(&latest) {
+            res.push(OutdatedDependency {
+                dep,
+                installed,
+                latest,
+            });
+        }
+    }
+
+    res
+}
+
+pub fn get_installed_deps<'a>() -> Vec<Dependency<'a>> {
+    get_deps()
+        .into_iter()
+        .filter(|dep| dep.is_installed())
+        .collect()
+}
+
+// Upgrades the given dependencies or all installed ones if `names` is empty
+pub fn update(names: Vec<String>) {
+    let deps = if names.is_empty() {
+        get_installed_deps()
+    } else {
+        get_deps_by_strings(names)
+    };
+
+    print_step("Checking for updates...");
+    let outdated = get_outdated(deps);
+
+    if outdated.is_empty() {
+        print_success("All dependencies are up to date.");
+        return;
+    }
+
+    install_deps(outdated.into_iter().map(|out| out.dep).collect(), None);
+}
+
 pub fn get_dir() -> PathBuf {
     config::get_dir().join("deps")
 }
@@ -353,11 +1177,50 @@ pub fn create_dir() {
     }
 }
 
+pub fn get_mirror() -> Option<String> {
+    config::get()
+        .deps
+        .mirror
+        .map(|mirror| mirror.trim_end_matches('/').to_string())
+}
+
+pub fn get_api_url() -> String {
+    get_mirror().unwrap_or("https://api.github.com".to_string())
+}
+
+pub fn get_archive_url() -> String {
+    get_mirror().unwrap_or("https://github.com".to_string())
+}
+
+// Asset URLs relative to the mirror are allowed, so local mirrors can be moved around
+pub fn resolve_mirror_url(url: &str) -> String {
+    let absolute =
+        http::is_remote(url) || url.starts_with("file://") || Path::new(url).is_absolute();
+
+    match get_mirror() {
+        Some(mirror) if !absolute => format!("{}/{}", mirror, url),
+        _ => url.to_string(),
+    }
+}
+
+// Whether dependencies can be resolved and downloaded with the current settings
+pub fn can_download() -> bool {
+    if !console::get_command_line_settings().offline {
+        return true;
+    }
+
+    match get_mirror() {
+        Some(mirror) => !http::is_remote(&mirror),
+        None => false,
+    }
+}
+
+pub fn get_gh_release_url(base: &str, owner: &str, repo: &str, release: &str) -> String {
+    format!("{}/repos/{}/{}/releases/{}", base, owner, repo, release)
+}
+
 pub fn fetch_gh_release(owner: &str, repo: &str, release: &str) -> GitHubRelease {
-    let url = format!(
-        "https://api.github.com/repos/{}/{}/releases/{}",
-        owner, repo, release
-    );
+    let url = get_gh_release_url(&get_api_url(), owner, repo, release);
 
     http::fetch_struct(url.as_str())
 }
@@ -366,6 +1229,10 @@ pub fn fetch_gh_latest_release(owner: &str, repo: &str) -> GitHubRelease {
     fetch_gh_release(owner, repo, "latest")
 }
 
+pub fn fetch_gh_tagged_release(owner: &str, repo: &str, tag: &str) -> GitHubRelease {
+    fetch_gh_release(owner, repo, format!("tags/{}", tag).as_str())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -387,6 +1254,137 @@ mod tests {
         }
     }
 
+    #[test]
+    fn versioned_file_names() {
+        let mut dep = get_dep_or_crash("love-win64");
+        assert_eq!(dep.get_file_name(), "love_win64.zip");
+
+        dep.set_version("11.5");
+        assert_eq!(dep.get_file_name(), "love_win64-11.5.zip");
+
+        let dep = get_dep_or_crash("love-linux@11.4");
+        assert_eq!(dep.get_file_name(), "love_linux-11.4.AppImage");
+
+        let dep = get_builtin_deps().remove(2);
+        assert_eq!(
+            dep.get_file_version("love_win64-11.4.zip"),
+            Some("11.4".to_string())
+        );
+        assert_eq!(dep.get_file_version("love_win64.zip"), None);
+        assert_eq!(dep.get_file_version("love_win64-11.4.zip.part"), None);
+        assert_eq!(dep.get_file_version("love_win32-11.4.zip"), None);
+    }
+
+    #[test]
+    fn project_files() {
+        let mut project =
+            ProjectConfig::parse_str(include_str!("testData/projects/dependencies.toml")).unwrap();
+        project.package.love_version = Some("11.4".to_string());
+
+        let mut lockfile = Lockfile::new();
+        lockfile.set(crate::lock::LockedDependency {
+            name: "love-linux".to_string(),
+            version: Some("11.3".to_string()),
+            tag: "11.3".to_string(),
+            url: String::new(),
+            sha256: String::new(),
+            source: None,
+        });
+
+        let files = get_project_files(&project, lockfile);
+
+        for name in [
+            "lib.zip",
+            "love_fork_win64.zip",
+            "love_win64-11.4.zip",
+            "love_linux-11.4.AppImage",
+            "love_linux-11.3.AppImage",
+        ] {
+            assert!(files.contains(&PathBuf::from(name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn custom_deps() {
+        let project =
+            ProjectConfig::parse_str(include_str!("testData/projects/dependencies.toml")).unwrap();
+
+        let fork = &project.dependencies["love-win64"];
+        assert!(fork.validate("love-win64").is_ok());
+
+        let dep = fork.to_dependency("love-win64");
+        assert_eq!(dep.get_path(), get_dir().join("love_fork_win64.zip"));
+        assert_eq!(dep.get_repo_url(), "https://github.com/someone/love-fork");
+
+        let lib = &project.dependencies["lib"];
+        assert!(lib.validate("lib").is_ok());
+        assert_eq!(lib.description, "Custom dependency");
+        assert!(matches!(
+            lib.to_dependency("lib").mode,
+            RepoDownload::Source("main")
+        ));
+
+        assert!(project.dependencies["broken"].validate("broken").is_err());
+    }
+
+    #[test]
+    fn installed_manifest() {
+        // Written by older versions, without tags
+        let old = "[[file]]\nname = \"rcedit.exe\"\nsize = 10\nsha256 = \"abc\"\n";
+        let manifest: InstalledManifest = toml::from_str(old).unwrap();
+        assert!(manifest.get_file("rcedit.exe").unwrap().tag.is_none());
+        assert!(manifest.get_file("rcedit.exe").unwrap().url.is_none());
+
+        let res = ResolvedDependency {
+            tag: "11.5".to_string(),
+            url: "https://github.com/love2d/love/releases/download/11.5/love-11.5-win64.zip"
+                .to_string(),
+            sha256: None,
+        };
+
+        assert_eq!(res.get_asset_name(), "love-11.5-win64.zip");
+    }
+
+    #[test]
+    fn mirror_server() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 1024];
+            let len = stream.read(&mut buf).unwrap();
+            let request = String::from_utf8_lossy(&buf[..len]).to_string();
+
+            let body = include_str!("testData/releases/love.json");
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+
+            stream.write_all(response.as_bytes()).unwrap();
+            request
+        });
+
+        let url = get_gh_release_url(&base, "love2d", "love", "latest");
+        let release: GitHubRelease = http::fetch_struct(url);
+        let request = server.join().unwrap();
+
+        assert!(request.starts_with("GET /repos/love2d/love/releases/latest "));
+        assert!(release.get_asset_matching(".*win64.zip").is_some());
+    }
+
+    #[test]
+    fn local_mirror() {
+        // `.json` is appended when the file without an extension doesn't exist
+        let release: GitHubRelease = http::fetch_struct("src/testData/releases/love");
+        assert!(release.get_asset_matching(".*x86_64.AppImage").is_some());
+    }
+
     #[test]
     fn deserialize_releases() {
         let data = vec![
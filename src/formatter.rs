@@ -0,0 +1,595 @@
+use ansi_term::Color::{Cyan, Green, Red};
+use serde::{Deserialize, Serialize};
+use std::{fs, process::exit};
+
+use crate::{
+    console::{exit_err, print_err, print_note, print_step, print_success, print_warn},
+    files,
+    lua_lexer::{self, Token, TokenKind},
+    lua_parser,
+    project_config::{Fmt, ProjectConfig},
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum IndentStyle {
+    Spaces,
+    Tabs,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteStyle {
+    Double,
+    Single,
+    Keep,
+}
+
+const OPENERS: [&str; 7] = ["function", "do", "then", "repeat", "{", "(", "["];
+const CLOSERS: [&str; 6] = ["end", "until", "}", ")", "]", "elseif"];
+
+// Longer diffs are shown as a replacement of the whole file
+const MAX_DIFF_EDITS: usize = 2000;
+
+fn is_any(token: &Token, texts: &[&str]) -> bool {
+    texts.iter().any(|text| token.is(text))
+}
+
+fn is_line_comment(token: &Token) -> bool {
+    token.kind == TokenKind::Comment && token.end_line == token.line
+}
+
+// Converts the quotes of a string, unless it would need more escaping
+fn convert_quotes(text: &str, style: QuoteStyle) -> String {
+    let (from, to) = match style {
+        QuoteStyle::Keep => return text.to_string(),
+        QuoteStyle::Double => ('\'', '"'),
+        QuoteStyle::Single => ('"', '\''),
+    };
+
+    if !text.starts_with(from) {
+        return text.to_string();
+    }
+
+    let mut res = String::from(to);
+    let mut chars = text[1..text.len() - 1].chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next) if next == from => res.push(next),
+                Some(next) => {
+                    res.push('\\');
+                    res.push(next);
+                }
+                None => res.push('\\'),
+            },
+            c if c == to => return text.to_string(),
+            c => res.push(c),
+        }
+    }
+
+    res.push(to);
+    res
+}
+
+// Text of the token as it's written by the formatter
+fn format_token(token: &Token, config: &Fmt) -> String {
+    match token.kind {
+        TokenKind::String => convert_quotes(&token.text, config.quote_style),
+        _ if is_line_comment(token) => token.text.trim_end().to_string(),
+        _ => token.text.to_owned(),
+    }
+}
+
+// Whether `-` or `#` is a unary operator, judging by the token before it
+fn is_unary(before: Option<&Token>) -> bool {
+    match before {
+        None => true,
+        Some(token) => match token.kind {
+            TokenKind::Symbol => !is_any(token, &[")", "]", "}", "..."]),
+            TokenKind::Keyword => !is_any(token, &["end", "true", "false", "nil"]),
+            _ => false,
+        },
+    }
+}
+
+fn needs_space(prev: &Token, next: &Token, before_prev: Option<&Token>) -> bool {
+    let (p, n) = (prev.text.as_str(), next.text.as_str());
+
+    if next.kind == TokenKind::Comment {
+        return true;
+    }
+
+    // Tokens that would merge into a comment or a long string
+    if (p.ends_with('-') && n.starts_with('-'))
+        || (p.ends_with('[') && (n.starts_with('[') || n.starts_with('=')))
+    {
+        return true;
+    }
+
+    if prev.is("(") || prev.is("[") || is_any(next, &[")", "]", ",", ";"]) {
+        return false;
+    }
+
+    if is_any(prev, &[".", ":"]) || is_any(next, &[".", ":"]) {
+        return false;
+    }
+
+    // `::label::`
+    if (prev.is("::") && next.kind == TokenKind::Name)
+        || (next.is("::")
+            && prev.kind == TokenKind::Name
+            && before_prev.is_some_and(|t| t.is("::")))
+    {
+        return false;
+    }
+
+    let is_callee = prev.kind == TokenKind::Name || is_any(prev, &[")", "]"]);
+
+    if next.is("(") {
+        return !(is_callee || prev.is("function"));
+    }
+
+    if next.is("[") {
+        return !is_callee;
+    }
+
+    if prev.is("{") {
+        return !next.is("}");
+    }
+
+    if (prev.is("-") && is_unary(before_prev)) || prev.is("#") {
+        return false;
+    }
+
+    true
+}
+
+// Formats the code, keeping its line breaks.
+// Fails if the code is invalid or formatting would change more than whitespace and quotes.
+pub fn format_code(code: &str, config: &Fmt) -> Result<String, String> {
+    let (chunk, errors) = lua_parser::parse(code);
+
+    if let Some(err) = errors.first() {
+        return Err(format!("Syntax error at {}", err));
+    }
+
+    let newline = if code.contains("\r\n") { "\r\n" } else { "\n" };
+    let indent_unit = match config.indent_style {
+        IndentStyle::Spaces => " ".repeat(config.indent_width),
+        IndentStyle::Tabs => "\t".to_string(),
+    };
+
+    let mut res = String::new();
+
+    // The lexer skips the shebang
+    if code.starts_with('#') {
+        res += code.lines().next().unwrap_or("").trim_end();
+        res += newline;
+    }
+
+    let tokens: Vec<&Token> = chunk
+        .tokens
+        .iter()
+        .filter(|token| token.kind != TokenKind::Eof)
+        .collect();
+
+    let mut stack: Vec<bool> = Vec::new(); // open constructs, and whether each indents
+    let mut prev: Option<&Token> = None;
+    let mut last_codes: (Option<&Token>, Option<&Token>) = (None, None); // may be on previous lines
+    let mut start = 0;
+
+    while start < tokens.len() {
+        let mut end = start + 1;
+
+        while end < tokens.len() && tokens[end].line <= tokens[end - 1].end_line {
+            end += 1;
+        }
+
+        let line = &tokens[start..end];
+        start = end;
+
+        if let Some(prev) = prev {
+            let gap = line[0].line - prev.end_line;
+            res += &newline.repeat(gap.clamp(1, 2));
+        }
+
+        // Closing tokens at the start of the line are not indented
+        let mut leading = 0;
+
+        for token in line {
+            if !is_any(token, &CLOSERS) && !token.is("else") {
+                break;
+            }
+
+            stack.pop();
+            leading += 1;
+
+            if token.is("else") {
+                break;
+            }
+        }
+
+        res += &indent_unit.repeat(stack.iter().filter(|indents| **indents).count());
+
+        let mut min_len = stack.len();
+
+        if leading != 0 && line[leading - 1].is("else") {
+            stack.push(false);
+        }
+
+        for (index, token) in line.iter().enumerate() {
+            if index != 0 {
+                let before = match line[index - 1].kind {
+                    TokenKind::Comment => None,
+                    _ => last_codes.0,
+                };
+
+                if needs_space(line[index - 1], token, before) {
+                    res.push(' ');
+                }
+            }
+
+            res += &format_token(token, config);
+
+            if index >= leading {
+                if is_any(token, &CLOSERS) || token.is("else") {
+                    stack.pop();
+                    min_len = min_len.min(stack.len());
+                }
+
+                if is_any(token, &OPENERS) || token.is("else") {
+                    stack.push(false);
+                }
+            }
+
+            prev = Some(token);
+
+            if token.kind != TokenKind::Comment {
+                last_codes = (last_codes.1, Some(token));
+            }
+        }
+
+        // Only one level of indentation per line, even if more constructs were opened.
+        // The innermost one indents, since it's closed first.
+        if stack.len() > min_len {
+            *stack.last_mut().unwrap() = true;
+        }
+    }
+
+    if prev.is_some() {
+        res += newline;
+    }
+
+    verify(&chunk.tokens, &res, config)?;
+    Ok(res)
+}
+
+// Makes sure only whitespace and quotes were changed
+fn verify(original: &[Token], formatted: &str, config: &Fmt) -> Result<(), String> {
+    let (tokens, errors) = lua_lexer::tokenize(formatted);
+
+    let expected: Vec<(TokenKind, String)> = original
+        .iter()
+        .map(|token| (token.kind, format_token(token, config)))
+        .collect();
+
+    let actual: Vec<(TokenKind, String)> = tokens
+        .iter()
+        .map(|token| (token.kind, token.text.to_owned()))
+        .collect();
+
+    if !errors.is_empty() || expected != actual {
+        return Err("Formatting would change the meaning of the code".to_string());
+    }
+
+    Ok(())
+}
+
+enum Edit<'a> {
+    Keep(&'a str),
+    Remove(&'a str),
+    Add(&'a str),
+}
+
+// Shortest edit script between the lines (Myers' algorithm)
+fn diff_lines<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<Edit<'a>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let offset = (n + m + 1) as usize;
+    let mut v = vec![0isize; 2 * offset + 1];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    let index = |k: isize| (k + offset as isize) as usize;
+
+    'search: for d in 0..=(n + m) {
+        if d as usize > MAX_DIFF_EDITS {
+            let mut res: Vec<Edit> = a.iter().map(|line| Edit::Remove(line)).collect();
+            res.extend(b.iter().map(|line| Edit::Add(line)));
+            return res;
+        }
+
+        trace.push(v.clone());
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+
+            let mut y = x - k;
+
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+
+            v[index(k)] = x;
+
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let (mut x, mut y) = (n, m);
+    let mut res = Vec::new();
+
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+
+        let prev_k = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+
+        let prev_x = v[index(prev_k)];
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            res.push(Edit::Keep(a[x as usize - 1]));
+            x -= 1;
+            y -= 1;
+        }
+
+        if d > 0 {
+            if x == prev_x {
+                res.push(Edit::Add(b[y as usize - 1]));
+                y -= 1;
+            } else {
+                res.push(Edit::Remove(a[x as usize - 1]));
+                x -= 1;
+            }
+        }
+    }
+
+    res.reverse();
+    res
+}
+
+// Unified diff with 3 lines of context
+pub fn diff(old: &str, new: &str) -> String {
+    const CONTEXT: usize = 3;
+
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let edits = diff_lines(&a, &b);
+
+    let changes: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| !matches!(edit, Edit::Keep(_)))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut res = String::new();
+    let mut c = 0;
+
+    while c < changes.len() {
+        let start = changes[c].saturating_sub(CONTEXT);
+        let mut end = changes[c];
+
+        // Joins changes with overlapping context
+        while c < changes.len() && changes[c] <= end + 2 * CONTEXT {
+            end = changes[c];
+            c += 1;
+        }
+
+        let end = (end + CONTEXT + 1).min(edits.len());
+
+        let count_before = |keep_removed: bool| {
+            edits[..start]
+                .iter()
+                .filter(|edit| match edit {
+                    Edit::Keep(_) => true,
+                    Edit::Remove(_) => keep_removed,
+                    Edit::Add(_) => !keep_removed,
+                })
+                .count()
+        };
+
+        let hunk = &edits[start..end];
+        let old_len = hunk.iter().filter(|e| !matches!(e, Edit::Add(_))).count();
+        let new_len = hunk
+            .iter()
+            .filter(|e| !matches!(e, Edit::Remove(_)))
+            .count();
+
+        res += &format!(
+            "@@ -{},{} +{},{} @@\n",
+            count_before(true) + 1,
+            old_len,
+            count_before(false) + 1,
+            new_len
+        );
+
+        for edit in hunk {
+            match edit {
+                Edit::Keep(line) => res += &format!(" {}\n", line),
+                Edit::Remove(line) => res += &format!("-{}\n", line),
+                Edit::Add(line) => res += &format!("+{}\n", line),
+            }
+        }
+    }
+
+    res
+}
+
+fn print_diff(path: &str, diff: &str) {
+    println!("{}", Red.paint(format!("--- {}", path)));
+    println!("{}", Green.paint(format!("+++ {} (formatted)", path)));
+
+    for line in diff.lines() {
+        let painted = match line.chars().next() {
+            Some('@') => Cyan.paint(line),
+            Some('-') => Red.paint(line),
+            Some('+') => Green.paint(line),
+            _ => line.into(),
+        };
+
+        println!("{}", painted);
+    }
+}
+
+// Formats the project's scripts. With `check`, only shows what would change and fails if anything would.
+pub fn format_project(project: &ProjectConfig, check: bool) {
+    let root = project.paths.get_root_dir();
+    let scripts = project.paths.get_lua_files();
+    let mut changed = 0;
+    let mut failed = 0;
+
+    if !check {
+        print_step("Formatting Lua scripts...");
+    }
+
+    for path in &scripts {
+        let relative = files::skip_path(path, &root);
+        let relative = relative.to_str().unwrap().replace('\\', "/");
+
+        let code = fs::read_to_string(path)
+            .unwrap_or_else(|err| exit_err(format!("Failed to read {}: {}", relative, err)));
+
+        let formatted = match format_code(&code, &project.fmt) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                print_warn(format!("Skipping {}: {}", relative, err));
+                failed += 1;
+                continue;
+            }
+        };
+
+        if formatted == code {
+            continue;
+        }
+
+        changed += 1;
+
+        if check {
+            print_diff(&relative, &diff(&code, &formatted));
+            continue;
+        }
+
+        fs::write(path, formatted)
+            .unwrap_or_else(|err| exit_err(format!("Failed to write {}: {}", relative, err)));
+
+        print_note(format!("Formatted {}", relative));
+    }
+
+    if check {
+        if changed != 0 {
+            print_err(format!(
+                "{} of {} scripts are not formatted. Run `lover fmt` to format them.",
+                changed,
+                scripts.len()
+            ));
+            exit(1);
+        }
+
+        if failed != 0 {
+            exit(1);
+        }
+
+        print_success("All scripts are formatted");
+        return;
+    }
+
+    print_success(format!(
+        "Formatted {} of {} scripts",
+        changed,
+        scripts.len()
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(code: &str) -> String {
+        format_code(code, &Fmt::default()).unwrap()
+    }
+
+    #[test]
+    fn formatting() {
+        let code = "local  t={1,2 ,x=- 3,['k']=#t}
+
+
+function love.update( dt )
+if t [1]==1 then
+print('it\\'s', \"ok\") -- note
+elseif not t then return end
+for i=1,10 do ::a:: goto a end
+foo(function()
+return a..b
+end)
+local s = [[
+  keep
+]]
+end";
+
+        let expected = "local t = { 1, 2, x = -3, [\"k\"] = #t }
+
+function love.update(dt)
+    if t[1] == 1 then
+        print(\"it's\", \"ok\") -- note
+    elseif not t then return end
+    for i = 1, 10 do ::a:: goto a end
+    foo(function()
+        return a .. b
+    end)
+    local s = [[
+  keep
+]]
+end
+";
+
+        assert_eq!(format(code), expected);
+        assert_eq!(format(expected), expected);
+
+        let tabs = Fmt {
+            indent_style: IndentStyle::Tabs,
+            ..Fmt::default()
+        };
+
+        assert_eq!(
+            format_code("if a then\r\nb = - -1\r\nend", &tabs).unwrap(),
+            "if a then\r\n\tb = - -1\r\nend\r\n"
+        );
+    }
+
+    #[test]
+    fn quotes_and_diff() {
+        assert_eq!(convert_quotes("'a\\'b'", QuoteStyle::Double), "\"a'b\"");
+        assert_eq!(
+            convert_quotes("'say \"hi\"'", QuoteStyle::Double),
+            "'say \"hi\"'"
+        );
+        assert_eq!(convert_quotes("\"x\"", QuoteStyle::Single), "'x'");
+        assert!(format_code("x = = 1", &Fmt::default()).is_err());
+
+        let diff = diff("a\nb\nc\n", "a\nB\nc\nd\n");
+        assert_eq!(diff, "@@ -1,3 +1,4 @@\n a\n-b\n+B\n c\n+d\n");
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    actions::CommandRunner,
+    console::{exit_err, print_err, print_note, print_warn},
+    files,
+    project_config::ProjectConfig,
+};
+
+// File the game's stdout and stderr are copied to
+pub struct GameLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl GameLog {
+    pub fn create(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            files::create_dir(parent);
+        }
+
+        let file = File::create(&path).unwrap_or_else(|err| {
+            exit_err(format!(
+                "Failed to create log '{}': {}",
+                path.to_str().unwrap(),
+                err
+            ));
+        });
+
+        Self {
+            path,
+            file: Mutex::new(file),
+        }
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    // Colors of the terminal output are not saved
+    pub fn write_line(&self, line: &str) {
+        let line = strip_ansi_escapes::strip_str(line);
+
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(file, "{}", line);
+        }
+    }
+}
+
+pub fn get_dir(project: Option<&ProjectConfig>) -> PathBuf {
+    match project {
+        Some(project) => project.paths.get_build_dir().join("logs"),
+        None => std::env::temp_dir().join("lover_logs"),
+    }
+}
+
+// UTC time formatted as `YYYY-MM-DD_HH-MM-SS`, so log names sort chronologically
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let time = secs % 86400;
+
+    // Converts days since 1970-01-01 to a civil date
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+// Removes the oldest logs so at most `keep` of them remain
+pub fn rotate(dir: &Path, keep: usize) {
+    let mut logs: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+            .collect(),
+        Err(_) => return,
+    };
+
+    if logs.len() <= keep {
+        return;
+    }
+
+    logs.sort();
+
+    for path in &logs[..logs.len() - keep] {
+        if let Err(err) = fs::remove_file(path) {
+            print_warn(format!(
+                "Failed to remove old log '{}': {}",
+                path.to_str().unwrap(),
+                err
+            ));
+        }
+    }
+}
+
+// Log at `path`, or a new timestamped log in the log directory.
+// Only timestamped logs are rotated.
+pub fn open(path: Option<String>, project: Option<&ProjectConfig>, max_logs: usize) -> GameLog {
+    if let Some(path) = path {
+        return GameLog::create(path);
+    }
+
+    let dir = get_dir(project);
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0);
+
+    // Leaves space for the new log
+    rotate(&dir, max_logs.saturating_sub(1));
+
+    GameLog::create(dir.join(format!("{}.log", format_timestamp(secs))))
+}
+
+// Prints every line of the output with a prefix and copies it to the log
+pub fn forward(output: impl Read, prefix: &str, stderr: bool, log: Option<&GameLog>) {
+    let mut reader = BufReader::new(output);
+    let mut line: Vec<u8> = Vec::new();
+
+    while reader.read_until(b'\n', &mut line).unwrap_or(0) != 0 {
+        let text = String::from_utf8_lossy(&line);
+        let text = format!("{}{}", prefix, text.trim_end_matches(['\r', '\n']));
+
+        if stderr {
+            eprintln!("{}", text);
+        } else {
+            println!("{}", text);
+        }
+
+        if let Some(log) = log {
+            log.write_line(&text);
+        }
+
+        line.clear();
+    }
+}
+
+// Runs the game and copies its output to the log. Returns whether the game exited successfully.
+pub fn run(cmd: &CommandRunner, log: &GameLog) -> bool {
+    log.write_line(&format!("> {}", cmd.to_string()));
+
+    let mut child = cmd.spawn_piped().unwrap_or_else(|err| exit_err(err));
+
+    thread::scope(|scope| {
+        if let Some(stdout) = child.stdout.take() {
+            scope.spawn(move || forward(stdout, "", false, Some(log)));
+        }
+
+        if let Some(stderr) = child.stderr.take() {
+            scope.spawn(move || forward(stderr, "", true, Some(log)));
+        }
+    });
+
+    let success = match child.wait() {
+        Ok(status) => {
+            log.write_line(&format!("> Exited with {}", status));
+
+            if !status.success() {
+                print_err(format!("The game exited with {}", status));
+            }
+
+            status.success()
+        }
+        Err(err) => {
+            print_err(err.to_string());
+            false
+        }
+    };
+
+    print_note(format!(
+        "Log saved to '{}'",
+        log.get_path().to_str().unwrap()
+    ));
+
+    success
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01_00-00-00");
+        assert_eq!(format_timestamp(951782400), "2000-02-29_00-00-00");
+        assert_eq!(format_timestamp(1792339445), "2026-10-18_16-04-05");
+    }
+
+    #[test]
+    fn rotate_logs() {
+        let dir = std::env::temp_dir().join("lover_test_logs");
+        let _ = fs::remove_dir_all(&dir);
+        files::create_dir(&dir);
+
+        for secs in [300, 100, 200] {
+            fs::write(dir.join(format!("{}.log", format_timestamp(secs))), "").unwrap();
+        }
+
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        rotate(&dir, 2);
+
+        assert!(!dir.join(format!("{}.log", format_timestamp(100))).exists());
+        assert!(dir.join(format!("{}.log", format_timestamp(200))).exists());
+        assert!(dir.join(format!("{}.log", format_timestamp(300))).exists());
+        assert!(dir.join("notes.txt").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
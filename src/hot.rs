@@ -0,0 +1,179 @@
+use std::{
+    io::{ErrorKind, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    actions::CommandRunner,
+    console::{exit_err, print_note, print_success},
+    files,
+    project_config::ProjectConfig,
+    targets, watch,
+};
+
+// Sends changed Lua modules to the hot reload client injected into the game (lua/hot.lua).
+// The protocol is one line per module: `reload<TAB>module<TAB>absolute path`.
+pub struct HotServer {
+    listener: TcpListener,
+    clients: Vec<TcpStream>,
+}
+
+impl HotServer {
+    pub fn new() -> Result<Self, String> {
+        let listener = TcpListener::bind("127.0.0.1:0").map_err(|err| err.to_string())?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            listener,
+            clients: Vec::new(),
+        })
+    }
+
+    pub fn get_port(&self) -> u16 {
+        self.listener
+            .local_addr()
+            .map(|addr| addr.port())
+            .unwrap_or(0)
+    }
+
+    pub fn accept_clients(&mut self) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    let _ = stream.set_nonblocking(false);
+                    self.clients.push(stream);
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => break,
+            }
+        }
+    }
+
+    // Returns the amount of clients that received the message. Disconnected clients are dropped.
+    pub fn send(&mut self, message: &str) -> usize {
+        self.accept_clients();
+        self.clients
+            .retain_mut(|client| client.write_all(message.as_bytes()).is_ok());
+
+        self.clients.len()
+    }
+
+    pub fn send_reload(&mut self, module: &str, path: &Path) -> usize {
+        self.send(&format!("reload\t{}\t{}\n", module, path.display()))
+    }
+
+    // Reloads the changed files if all of them are Lua modules.
+    // Returns false if the game has to be restarted instead.
+    pub fn reload(&mut self, main: &Path, changed: &Vec<PathBuf>) -> bool {
+        let mut modules: Vec<(String, &PathBuf)> = Vec::new();
+
+        for path in changed {
+            match get_module_name(&files::skip_path(path, main)) {
+                Some(module) if path.is_file() => modules.push((module, path)),
+                _ => return false,
+            }
+        }
+
+        for (module, path) in &modules {
+            if self.send_reload(module, path) == 0 {
+                return false;
+            }
+        }
+
+        let names: Vec<&str> = modules.iter().map(|(name, _)| name.as_str()).collect();
+        print_success(format!("Hot reloaded: {}", names.join(", ")));
+
+        true
+    }
+}
+
+// Name used to `require` a script, relative to the main directory.
+// `main.lua` and `conf.lua` are not modules.
+pub fn get_module_name(relative: &Path) -> Option<String> {
+    if relative.extension()? != "lua" {
+        return None;
+    }
+
+    let path = relative
+        .with_extension("")
+        .to_str()?
+        .replace('\\', "/")
+        .trim_end_matches("/init")
+        .to_string();
+
+    match path.as_str() {
+        "main" | "conf" => None,
+        _ => Some(path.replace('/', ".")),
+    }
+}
+
+// Runs the game built with the hot reload client and rebuilds it when a file other than a Lua
+// module changes
+pub fn run(cmd: &mut CommandRunner, project: &ProjectConfig, run_args: Vec<String>, debug: bool) {
+    let mut server = HotServer::new().unwrap_or_else(|err| {
+        exit_err(format!("Failed to start the hot reload server: {}", err));
+    });
+
+    let main = project.paths.get_main_dir();
+    let love = targets::get_run_love_path(project);
+    let options = targets::ModuleOptions { hot: true, debug };
+
+    cmd.set_env("LOVER_HOT_PORT", server.get_port().to_string());
+    cmd.set_env("LOVER_HOT_ROOT", main.to_str().unwrap());
+    cmd.add_arg(love.to_str().unwrap());
+    cmd.add_args(run_args);
+
+    let build = || {
+        targets::build_run_love(project, &options);
+    };
+
+    print_note("Lua modules are reloaded in love.update. Other changes restart the game.");
+    watch::run(cmd, &main, Some(&project.paths), Some(&mut server), build);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+
+    #[test]
+    fn module_names() {
+        assert_eq!(get_module_name(Path::new("player.lua")).unwrap(), "player");
+        assert_eq!(
+            get_module_name(Path::new("src/enemies/init.lua")).unwrap(),
+            "src.enemies"
+        );
+        assert!(get_module_name(Path::new("main.lua")).is_none());
+        assert!(get_module_name(Path::new("assets/player.png")).is_none());
+    }
+
+    #[test]
+    fn stub_client() {
+        let mut server = HotServer::new().unwrap();
+        let client = TcpStream::connect(("127.0.0.1", server.get_port())).unwrap();
+        let mut reader = BufReader::new(client);
+
+        // The client may not be accepted immediately
+        let mut received = 0;
+
+        for _ in 0..50 {
+            received = server.send_reload("src.player", Path::new("/game/src/player.lua"));
+
+            if received != 0 {
+                break;
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        assert_eq!(received, 1);
+
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+
+        assert_eq!(line, "reload\tsrc.player\t/game/src/player.lua\n");
+    }
+}
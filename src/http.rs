@@ -1,31 +1,235 @@
-use reqwest::blocking::{Client, Response};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{HeaderMap, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::{Proxy, StatusCode, Url};
 use serde::de::DeserializeOwned;
+use serde::Deserialize;
 use std::{
-    fs::File,
+    fs::{self, File, OpenOptions},
     io::{Read, Write},
-    path::Path,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use crate::console::{exit_err, ProgressBar};
+use crate::config;
+use crate::console::{exit_err, get_command_line_settings};
+use crate::files;
 
 pub struct Downloadable {
-    response: Response,
-    //url: String
+    url: String,
+    source: Option<Box<dyn Read + Send>>, // response of the initial request
+    len: Option<u64>,
+    sha256: Option<String>,
+    validator: Option<String>, // ETag or Last-Modified of the file, used to resume safely
 }
 
 impl<'a> Downloadable {
+    // Accepts HTTP(S) URLs and local paths
     pub fn request(url: impl Into<String>) -> Self {
+        let url: String = url.into();
+
+        if !is_remote(&url) {
+            let path = to_local_path(&url);
+            let file = files::open(&path);
+            let len = file.metadata().map(|meta| meta.len()).ok();
+
+            return Self {
+                url,
+                source: Some(Box::new(file)),
+                len,
+                sha256: None,
+                validator: None,
+            };
+        }
+
+        let response = get_request(&url);
+        let headers = response.headers();
+
+        let validator = headers
+            .get(ETAG)
+            .or_else(|| headers.get(LAST_MODIFIED))
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
         Self {
-            response: get_request(url), //url: url
+            url,
+            len: response.content_length(),
+            source: Some(Box::new(response)),
+            sha256: None,
+            validator,
+        }
+    }
+
+    // What a `.part` file was downloaded from, stored next to it
+    fn get_part_source(&self) -> String {
+        format!(
+            "{}\n{}",
+            self.url,
+            self.validator.as_deref().unwrap_or_default()
+        )
+    }
+
+    pub fn set_sha256(&mut self, hash: Option<String>) -> &mut Self {
+        self.sha256 = hash;
+        self
+    }
+
+    // Downloads into a `.part` file first and moves it to `path` only once it's verified.
+    // Interrupted downloads are retried and resumed from the `.part` file.
+    // `on_progress` receives the amount of downloaded bytes.
+    // Returns the SHA-256 of the downloaded file.
+    pub fn download(&mut self, path: &Path, on_progress: impl Fn(usize)) -> Result<String, String> {
+        let retries = config::get().deps.retries;
+        let part_path = get_part_path(path);
+        let source_path = get_part_source_path(&part_path);
+        let mut attempt: u32 = 0;
+
+        // A `.part` file of another URL or an older version of the file can't be resumed
+        if is_remote(&self.url) {
+            let source = self.get_part_source();
+
+            if fs::read_to_string(&source_path).ok() != Some(source.to_owned()) {
+                let _ = fs::remove_file(&part_path);
+            }
+
+            fs::write(&source_path, source)
+                .map_err(|err| format!("Failed to write '{}': {}", source_path.display(), err))?;
+        }
+
+        loop {
+            match self.transfer(&part_path, &on_progress) {
+                Ok(()) => break,
+                Err(err) => {
+                    if attempt >= retries {
+                        return Err(err);
+                    }
+
+                    attempt += 1;
+                    thread::sleep(Duration::from_secs(2u64.pow(attempt - 1)));
+                }
+            }
+        }
+
+        let size = fs::metadata(&part_path)
+            .map(|meta| meta.len())
+            .map_err(|err| err.to_string())?;
+
+        if let Some(expected_len) = self.len {
+            if size != expected_len {
+                remove_part(&part_path);
+
+                return Err(format!(
+                    "Download of '{}' is incomplete: received {} of {} bytes",
+                    path.display(),
+                    size,
+                    expected_len
+                ));
+            }
+        }
+
+        let hash = sha256::try_digest(&part_path).map_err(|err| {
+            format!(
+                "Failed to calculate checksum of '{}': {}",
+                part_path.display(),
+                err
+            )
+        })?;
+
+        if let Some(expected) = &self.sha256 {
+            if !hash.eq_ignore_ascii_case(expected) {
+                remove_part(&part_path);
+
+                return Err(format!(
+                    "Checksum mismatch of '{}'. Expected {}, got {}",
+                    path.display(),
+                    expected,
+                    hash
+                ));
+            }
         }
+
+        fs::rename(&part_path, path).map_err(|err| {
+            format!(
+                "Failed to move '{}' to '{}': {}",
+                part_path.display(),
+                path.display(),
+                err
+            )
+        })?;
+
+        let _ = fs::remove_file(&source_path);
+
+        Ok(hash)
     }
 
-    pub fn download(&mut self, path: &Path, alias: impl Into<String>) {
-        download_response(&mut self.response, path, alias);
+    // Single download attempt
+    fn transfer(&mut self, part_path: &Path, on_progress: &impl Fn(usize)) -> Result<(), String> {
+        let mut offset: u64 = 0;
+
+        if is_remote(&self.url) && part_path.exists() {
+            offset = fs::metadata(part_path).map(|meta| meta.len()).unwrap_or(0);
+        }
+
+        let mut source: Box<dyn Read + Send> = match self.source.take() {
+            Some(source) if offset == 0 => source,
+            _ => {
+                if !is_remote(&self.url) {
+                    Box::new(File::open(to_local_path(&self.url)).map_err(|err| err.to_string())?)
+                } else {
+                    let response = try_get_request(&self.url, offset, self.validator.as_deref())?;
+
+                    if response.status() == StatusCode::PARTIAL_CONTENT {
+                        self.len = response.content_length().map(|len| len + offset);
+                    } else {
+                        // The server doesn't support resuming or the file changed, starting over
+                        offset = 0;
+                        self.len = response.content_length();
+                    }
+
+                    Box::new(response)
+                }
+            }
+        };
+
+        let mut file = if offset == 0 {
+            File::create(part_path)
+        } else {
+            OpenOptions::new().append(true).open(part_path)
+        }
+        .map_err(|err| format!("Failed to open '{}': {}", part_path.display(), err))?;
+
+        let mut bytes = offset as usize;
+        let mut buf = vec![0u8; 64 * 1024];
+
+        on_progress(bytes);
+
+        loop {
+            let bytes_read = source
+                .read(&mut buf)
+                .map_err(|err| format!("Read failed: {}", err))?;
+
+            if bytes_read == 0 {
+                break;
+            }
+
+            file.write_all(&buf[..bytes_read])
+                .map_err(|err| format!("Write failed: {}", err))?;
+
+            bytes += bytes_read;
+            on_progress(bytes);
+        }
+
+        match self.len {
+            Some(len) if (bytes as u64) < len => Err(format!(
+                "Connection closed after {} of {} bytes",
+                bytes, len
+            )),
+            _ => Ok(()),
+        }
     }
 
     pub fn len(&self) -> Option<u64> {
-        self.response.content_length()
+        self.len
     }
 }
 
@@ -33,84 +237,368 @@ pub fn get_user_agent() -> String {
     format!("Lover/{}", env!("CARGO_PKG_VERSION"))
 }
 
+pub fn is_remote(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+pub fn to_local_path(url: &str) -> PathBuf {
+    PathBuf::from(url.strip_prefix("file://").unwrap_or(url))
+}
+
+// Proxies from the `HTTP_PROXY`, `HTTPS_PROXY` and `ALL_PROXY` variables are used automatically
+pub fn get_client() -> Client {
+    let conf = config::get();
+
+    let timeout = match conf.deps.timeout {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    };
+
+    let mut builder = Client::builder()
+        .user_agent(get_user_agent())
+        .timeout(timeout);
+
+    if let Some(proxy_url) = &conf.deps.proxy {
+        let proxy = Proxy::all(proxy_url).unwrap_or_else(|err| {
+            exit_err(format!("Invalid proxy '{}': {}", proxy_url, err));
+        });
+
+        builder = builder.proxy(proxy);
+    }
+
+    builder.build().unwrap_or_else(|err| {
+        exit_err(format!("Failed to create HTTP client: {}", err));
+    })
+}
+
+pub fn get_github_token() -> Option<String> {
+    match std::env::var("GITHUB_TOKEN") {
+        Ok(token) if !token.is_empty() => Some(token),
+        _ => config::get().deps.github_token,
+    }
+}
+
+pub fn is_github_url(url: &str) -> bool {
+    match Url::parse(url) {
+        Ok(url) => matches!(url.host_str(), Some("github.com") | Some("api.github.com")),
+        Err(_) => false,
+    }
+}
+
+// The token is only sent to GitHub, never to mirrors
+pub fn create_request(url: &str) -> RequestBuilder {
+    let mut request = get_client().get(url);
+
+    if is_github_url(url) {
+        if let Some(token) = get_github_token() {
+            request = request.bearer_auth(token);
+        }
+    }
+
+    request
+}
+
+#[derive(Deserialize)]
+pub struct ApiError {
+    pub message: String,
+}
+
+pub fn get_error_message(url: &str, status: StatusCode, headers: &HeaderMap, body: &str) -> String {
+    let remaining = headers
+        .get("x-ratelimit-remaining")
+        .and_then(|value| value.to_str().ok());
+
+    let rate_limited = status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN && remaining == Some("0"));
+
+    if rate_limited {
+        let reset = headers
+            .get("x-ratelimit-reset")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .and_then(|reset| {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+                Some(reset.saturating_sub(now).div_ceil(60))
+            });
+
+        let mut msg = "GitHub API rate limit exceeded.".to_string();
+
+        if let Some(minutes) = reset {
+            msg += format!(" It resets in {} minute(s).", minutes).as_str();
+        }
+
+        msg += "\nSet the GITHUB_TOKEN environment variable or `github_token` in the [deps] section of Lover's config to raise the limit.";
+
+        return msg;
+    }
+
+    match serde_json::from_str::<ApiError>(body) {
+        Ok(err) => format!("'{}' responded with {}: {}", url, status, err.message),
+        Err(_) => format!("'{}' responded with {}", url, status),
+    }
+}
+
+pub fn check_response(url: &str, response: Response) -> Result<Response, String> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.text().unwrap_or_default();
+
+    Err(get_error_message(url, status, &headers, &body))
+}
+
+pub fn check_offline(url: &str) {
+    if is_remote(url) && get_command_line_settings().offline {
+        exit_err(format!("Cannot access '{}' in offline mode.", url));
+    }
+}
+
 pub fn fetch_text(url: impl Into<String>) -> String {
     let url_str = url.into();
 
-    let res = Client::new()
-        .get(&url_str)
-        .header("User-Agent", get_user_agent())
+    check_offline(&url_str);
+    try_fetch_text(&url_str).unwrap_or_else(|err| exit_err(err))
+}
+
+pub fn try_fetch_text(url: &str) -> Result<String, String> {
+    if !is_remote(url) {
+        let path = get_mirror_path(url);
+
+        return fs::read_to_string(&path)
+            .map_err(|err| format!("Failed to read '{}': {}", path.display(), err));
+    }
+
+    if get_command_line_settings().offline {
+        return Err(format!("Cannot access '{}' in offline mode.", url));
+    }
+
+    let res = create_request(url)
         .send()
-        .unwrap_or_else(|err| {
-            exit_err(format!("Failed to request '{}': {}", &url_str, err));
-        });
+        .map_err(|err| format!("Failed to request '{}': {}", url, err))?;
 
-    res.text().unwrap_or_else(|err| {
-        exit_err(format!("Failed to get text from '{}': {}", &url_str, err));
-    })
+    check_response(url, res)?
+        .text()
+        .map_err(|err| format!("Failed to get text from '{}': {}", url, err))
 }
 
 pub fn fetch_struct<T: DeserializeOwned>(url: impl Into<String>) -> T {
     let url_str = url.into();
 
-    serde_json::from_str(&fetch_text(url_str.to_owned())).unwrap_or_else(|err| {
-        exit_err(format!("Struct parse error of '{}': {}", url_str, err));
-    })
+    check_offline(&url_str);
+    try_fetch_struct(&url_str).unwrap_or_else(|err| exit_err(err))
 }
 
-pub fn get_request(url: impl Into<String>) -> Response {
-    let client = Client::new()
-        .get(url.into())
-        .header("User-Agent", get_user_agent());
+pub fn try_fetch_struct<T: DeserializeOwned>(url: &str) -> Result<T, String> {
+    serde_json::from_str(&try_fetch_text(url)?)
+        .map_err(|err| format!("Struct parse error of '{}': {}", url, err))
+}
 
-    client.send().unwrap_or_else(|err| {
+pub fn get_request(url: impl Into<String>) -> Response {
+    try_get_request(&url.into(), 0, None).unwrap_or_else(|err| {
         exit_err(format!("Request failed: {}", err));
     })
 }
 
-pub fn download_response(response: &mut Response, path: &Path, alias: impl Into<String>) {
-    let mut file = File::create(path).unwrap_or_else(|err| {
-        exit_err(format!(
-            "Failed to open '{}': {}",
-            path.to_str().unwrap(),
-            err
-        ));
-    });
+// Requests the content starting at `offset` bytes if it's not 0.
+// With a `validator` the server sends the whole file instead if it changed since.
+pub fn try_get_request(
+    url: &str,
+    offset: u64,
+    validator: Option<&str>,
+) -> Result<Response, String> {
+    check_offline(url);
 
-    let len = match response.content_length() {
-        Some(len) => len as usize,
-        None => 0,
-    };
+    let mut request = create_request(url);
 
-    let mut bar = ProgressBar::new(len);
-    bar.set_prefix(alias);
-    bar.memory_mode();
+    if offset != 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
 
-    let mut bytes: usize = 0;
+        if let Some(validator) = validator {
+            request = request.header(IF_RANGE, validator);
+        }
+    }
 
-    loop {
-        let mut buf: [u8; 1024] = [0; 1024];
+    let response = request.send().map_err(|err| err.to_string())?;
 
-        let bytes_read = response.read(&mut buf).unwrap_or_else(|err| {
-            exit_err(format!("Read failed: {}", err));
-        });
+    check_response(url, response)
+}
 
-        if bytes_read == 0 {
-            break;
-        }
+// Allows local mirrors to store API responses as `latest.json` instead of `latest`
+pub fn get_mirror_path(url: &str) -> PathBuf {
+    let path = to_local_path(url);
+
+    if path.is_file() {
+        return path;
+    }
+
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".json");
+
+    path.with_file_name(name)
+}
 
-        file.write_all(&buf[..bytes_read]).unwrap_or_else(|err| {
-            bar.finish();
-            exit_err(format!("Write failed: {}", err));
+pub fn get_part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap().to_os_string();
+    name.push(".part");
+
+    path.with_file_name(name)
+}
+
+pub fn get_part_source_path(part_path: &Path) -> PathBuf {
+    let mut name = part_path.file_name().unwrap().to_os_string();
+    name.push(".source");
+
+    part_path.with_file_name(name)
+}
+
+fn remove_part(part_path: &Path) {
+    let _ = fs::remove_file(part_path);
+    let _ = fs::remove_file(get_part_source_path(part_path));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Serves `body` with an ETag of `etag` for the given amount of requests.
+    // Returns the URL and the requested ranges.
+    fn serve(
+        body: &[u8],
+        etag: &'static str,
+        requests: usize,
+    ) -> (String, thread::JoinHandle<Vec<Option<usize>>>) {
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/file.zip", listener.local_addr().unwrap());
+        let body = body.to_vec();
+
+        let server = thread::spawn(move || {
+            let mut ranges: Vec<Option<usize>> = Vec::new();
+
+            for _ in 0..requests {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 2048];
+                let len = stream.read(&mut buf).unwrap();
+                let request = String::from_utf8_lossy(&buf[..len]).to_lowercase();
+
+                let range = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("range: bytes="))
+                    .map(|range| range.trim_end_matches('-').parse::<usize>().unwrap());
+
+                let if_range = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("if-range: "))
+                    .map(str::to_string);
+
+                // Like real servers, only honors the range if the file didn't change
+                let (status, content) = match range {
+                    Some(start) if if_range.is_none_or(|tag| tag == etag) => {
+                        ("206 Partial Content", &body[start..])
+                    }
+                    _ => ("200 OK", &body[..]),
+                };
+
+                let header = format!(
+                    "HTTP/1.1 {}\r\nETag: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    etag,
+                    content.len()
+                );
+
+                let _ = stream.write_all(header.as_bytes());
+                let _ = stream.write_all(content);
+
+                ranges.push(range);
+            }
+
+            ranges
         });
 
-        bytes += bytes_read;
+        (url, server)
+    }
+
+    #[test]
+    fn resume_download() {
+        let body: Vec<u8> = (0..100_000).map(|i| (i % 251) as u8).collect();
+        let dir = std::env::temp_dir().join(format!("lover-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
 
-        if bytes > len {
-            bytes = len;
-        }
+        let path = dir.join("file.zip");
+        let part_path = get_part_path(&path);
+
+        // Left over from an earlier download of the same file
+        let (url, server) = serve(&body, "\"v1\"", 2);
+        fs::write(&part_path, &body[..30_000]).unwrap();
+        fs::write(get_part_source_path(&part_path), format!("{}\n\"v1\"", url)).unwrap();
+
+        let hash = Downloadable::request(&url).download(&path, |_| {}).unwrap();
+
+        assert_eq!(server.join().unwrap(), vec![None, Some(30_000)]);
+        assert!(fs::read(&path).unwrap() == body, "Resumed file differs");
+        assert_eq!(hash, sha256::digest(&body));
+        assert!(!get_part_source_path(&part_path).exists());
+
+        // Left over from an older version of the file
+        let (url, server) = serve(&body, "\"v2\"", 1);
+        fs::write(&part_path, vec![0u8; 30_000]).unwrap();
+        fs::write(get_part_source_path(&part_path), format!("{}\n\"v1\"", url)).unwrap();
+
+        let hash = Downloadable::request(&url).download(&path, |_| {}).unwrap();
+
+        assert_eq!(server.join().unwrap(), vec![None]);
+        assert_eq!(hash, sha256::digest(&body));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-        bar.update(bytes);
+    #[test]
+    fn api_errors() {
+        let url = "https://api.github.com/repos/love2d/love/releases/latest";
+        let mut headers = HeaderMap::new();
+
+        headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+
+        let body = r#"{"message": "API rate limit exceeded for 127.0.0.1."}"#;
+        let msg = get_error_message(url, StatusCode::FORBIDDEN, &headers, body);
+        assert!(msg.contains("rate limit"));
+        assert!(msg.contains("GITHUB_TOKEN"));
+
+        let body = r#"{"message": "Not Found", "documentation_url": "https://docs.github.com"}"#;
+        let msg = get_error_message(url, StatusCode::NOT_FOUND, &HeaderMap::new(), body);
+        assert!(msg.ends_with("Not Found"));
+    }
+
+    #[test]
+    fn github_urls() {
+        assert!(is_github_url("https://api.github.com/repos/love2d/love"));
+        assert!(is_github_url(
+            "https://github.com/lovebrew/nest/archive/refs/heads/master.zip"
+        ));
+        assert!(!is_github_url(
+            "https://mirror.example.com/repos/love2d/love"
+        ));
     }
 
-    bar.finish();
+    #[test]
+    fn part_paths() {
+        let part = get_part_path(Path::new("deps/love_win64-11.5.zip"));
+        assert_eq!(part, PathBuf::from("deps/love_win64-11.5.zip.part"));
+    }
+
+    #[test]
+    fn mirror_paths() {
+        assert_eq!(
+            get_mirror_path("file:///mirror/repos/love2d/love/releases/tags/11.5"),
+            PathBuf::from("/mirror/repos/love2d/love/releases/tags/11.5.json")
+        );
+        assert_eq!(
+            get_mirror_path("/mirror/repos/love2d/love/releases/latest"),
+            PathBuf::from("/mirror/repos/love2d/love/releases/latest.json")
+        );
+    }
 }
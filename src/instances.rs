@@ -0,0 +1,151 @@
+use ansi_term::Color::{self, Blue, Cyan, Green, Purple, Yellow};
+use std::{
+    path::{Path, PathBuf},
+    process::Child,
+    thread,
+};
+
+use crate::{
+    actions::CommandRunner,
+    console::{exit_err, print_err, print_note, print_warn},
+    files,
+    game_log::{self, GameLog},
+    project_config::Run,
+};
+
+const COLORS: [Color; 5] = [Cyan, Green, Yellow, Purple, Blue];
+
+// Environment variable LOVE uses to find the save directory
+pub fn get_data_env() -> Option<&'static str> {
+    #[cfg(target_os = "linux")]
+    return Some("XDG_DATA_HOME");
+
+    #[cfg(target_os = "windows")]
+    return Some("APPDATA");
+
+    #[allow(unreachable_code)]
+    None
+}
+
+pub fn get_prefix(instance: usize) -> String {
+    COLORS[(instance - 1) % COLORS.len()]
+        .paint(format!("[{}]", instance))
+        .to_string()
+}
+
+pub fn parse_count(value: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(count) if count > 0 => count,
+        _ => exit_err(format!(
+            "Invalid amount of instances '{}'. Expected a positive number.",
+            value
+        )),
+    }
+}
+
+fn start(cmd: &CommandRunner, instance: usize, run: &Run, data_dir: &Path) -> Option<Child> {
+    let mut cmd = cmd.clone();
+
+    cmd.set_env("LOVER_INSTANCE", instance.to_string());
+    cmd.add_args(run.get_instance_args(instance));
+
+    if let Some(var) = get_data_env() {
+        let dir = data_dir.join(instance.to_string());
+        files::create_dir(&dir);
+
+        cmd.set_env(var, dir.to_str().unwrap());
+    }
+
+    match cmd.spawn_piped() {
+        Ok(child) => Some(child),
+        Err(err) => {
+            print_err(format!("{} {}", get_prefix(instance), err));
+            None
+        }
+    }
+}
+
+// Runs `count` copies of the game and waits for all of them to close.
+// Each instance saves into its own directory inside `data_dir`.
+pub fn run(cmd: &CommandRunner, count: usize, run: &Run, data_dir: PathBuf, log: Option<&GameLog>) {
+    if get_data_env().is_none() {
+        print_warn("Instances share the save directory on this platform.");
+    }
+
+    print_note(format!(
+        "Running {} instances. LOVER_INSTANCE is set to the instance number.",
+        count
+    ));
+
+    let children: Vec<(usize, Child)> = (1..=count)
+        .filter_map(|instance| start(cmd, instance, run, &data_dir).map(|child| (instance, child)))
+        .collect();
+
+    if children.is_empty() {
+        exit_err("Failed to start the game.");
+    }
+
+    thread::scope(|scope| {
+        for (instance, mut child) in children {
+            let prefix = format!("{} ", get_prefix(instance));
+
+            let stdout = child.stdout.take().map(|stdout| {
+                let prefix = prefix.to_owned();
+                scope.spawn(move || game_log::forward(stdout, &prefix, false, log))
+            });
+
+            let stderr = child.stderr.take().map(|stderr| {
+                let prefix = prefix.to_owned();
+                scope.spawn(move || game_log::forward(stderr, &prefix, true, log))
+            });
+
+            // The output is printed before the exit status
+            scope.spawn(move || {
+                stdout.map(|handle| handle.join());
+                stderr.map(|handle| handle.join());
+
+                match child.wait() {
+                    Ok(status) if !status.success() => {
+                        let message = format!("{}Exited with {}", prefix, status);
+
+                        print_warn(&message);
+                        log.inspect(|log| log.write_line(&message));
+                    }
+                    Err(err) => print_err(format!("{}{}", prefix, err)),
+                    _ => (),
+                }
+            });
+        }
+    });
+
+    if let Some(log) = log {
+        print_note(format!(
+            "Log saved to '{}'",
+            log.get_path().to_str().unwrap()
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instance_args() {
+        let run: Run = toml::from_str(
+            r#"
+            default_args = ["--debug"]
+
+            [instances]
+            1 = ["--host"]
+            2 = ["--join", "localhost"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(run.get_instance_args(1), vec!["--host"]);
+        assert_eq!(run.get_instance_args(2), vec!["--join", "localhost"]);
+        assert!(run.get_instance_args(3).is_empty());
+        assert_eq!(parse_count("4"), 4);
+    }
+}
@@ -0,0 +1,823 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display},
+    fs,
+    path::{Path, PathBuf},
+    process::exit,
+};
+
+use crate::{
+    console::{exit_err, print_err, print_success, print_warn},
+    files, lock,
+    love_api::{ApiDatabase, ApiProblem},
+    lua_deps,
+    lua_parser::{self, Block, Expr, Field, Function, Name, Pos, Stat},
+    project_config::ProjectConfig,
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Off,
+    Warning,
+    Error,
+}
+
+// Rules with their default severity
+pub const RULES: [(&str, Severity); 8] = [
+    ("syntax", Severity::Error),
+    ("undefined-global", Severity::Error),
+    ("accidental-global", Severity::Warning),
+    ("unknown-love-api", Severity::Error),
+    ("deprecated-love-api", Severity::Warning),
+    ("changed-love-api", Severity::Warning),
+    ("unused-local", Severity::Warning),
+    ("unresolved-require", Severity::Error),
+];
+
+// Globals of LuaJIT and LOVE
+const STD_GLOBALS: &[&str] = &[
+    "_G",
+    "_VERSION",
+    "arg",
+    "assert",
+    "bit",
+    "collectgarbage",
+    "coroutine",
+    "debug",
+    "dofile",
+    "error",
+    "gcinfo",
+    "getfenv",
+    "getmetatable",
+    "io",
+    "ipairs",
+    "jit",
+    "load",
+    "loadfile",
+    "loadstring",
+    "love",
+    "math",
+    "module",
+    "newproxy",
+    "next",
+    "os",
+    "package",
+    "pairs",
+    "pcall",
+    "print",
+    "rawequal",
+    "rawget",
+    "rawset",
+    "require",
+    "select",
+    "setfenv",
+    "setmetatable",
+    "string",
+    "table",
+    "tonumber",
+    "tostring",
+    "type",
+    "unpack",
+    "xpcall",
+];
+
+// Modules `require` finds outside of the project
+const BUILTIN_MODULES: &[&str] = &[
+    "bit",
+    "enet",
+    "ffi",
+    "jit",
+    "ltn12",
+    "mime",
+    "socket",
+    "string.buffer",
+    "table.clear",
+    "table.new",
+];
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Issue {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    pub rule: String,
+    pub message: String,
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {} [{}]",
+            self.file, self.line, self.column, self.message, self.rule
+        )
+    }
+}
+
+struct Local {
+    name: Name,
+    used: bool,
+    check_unused: bool,
+}
+
+// What a file does with globals, the LOVE API and modules
+#[derive(Default)]
+struct FileInfo {
+    global_reads: Vec<Name>,
+    global_writes: Vec<(Name, bool)>, // and whether it's inside a function
+    love_reads: Vec<(String, Pos)>,
+    love_writes: Vec<String>,
+    requires: Vec<(String, Pos)>,
+    unused_locals: Vec<Name>,
+}
+
+// Walks the syntax tree keeping track of the local variables in scope
+#[derive(Default)]
+struct Walker {
+    scopes: Vec<Vec<usize>>,
+    locals: Vec<Local>,
+    depth: usize, // nesting of functions
+    info: FileInfo,
+}
+
+impl Walker {
+    fn resolve(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|index| self.locals[**index].name.name == name)
+            .copied()
+    }
+
+    fn declare(&mut self, name: &Name, check_unused: bool) {
+        self.locals.push(Local {
+            name: name.to_owned(),
+            used: false,
+            check_unused,
+        });
+
+        let index = self.locals.len() - 1;
+        self.scopes.last_mut().unwrap().push(index);
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn pop_scope(&mut self) {
+        for index in self.scopes.pop().unwrap() {
+            let local = &self.locals[index];
+
+            if local.check_unused && !local.used && !local.name.name.starts_with('_') {
+                self.info.unused_locals.push(local.name.to_owned());
+            }
+        }
+    }
+
+    // `love.x.y` where `love` is the global
+    fn get_love_path(&self, expr: &Expr) -> Option<String> {
+        let path = expr.get_path()?;
+
+        (path.starts_with("love.") && self.resolve("love").is_none()).then_some(path)
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.push_scope();
+        self.stats(block);
+        self.pop_scope();
+    }
+
+    fn stats(&mut self, block: &Block) {
+        for stat in &block.stats {
+            self.stat(stat);
+        }
+    }
+
+    fn write(&mut self, name: &Name) {
+        if self.resolve(&name.name).is_none() {
+            let in_function = self.depth != 0;
+            self.info.global_writes.push((name.to_owned(), in_function));
+        }
+    }
+
+    fn function(&mut self, func: &Function) {
+        self.depth += 1;
+        self.push_scope();
+
+        for param in &func.params {
+            self.declare(param, false);
+        }
+
+        self.block(&func.block);
+        self.pop_scope();
+        self.depth -= 1;
+    }
+
+    fn stat(&mut self, stat: &Stat) {
+        match stat {
+            Stat::Local { names, exprs } => {
+                self.exprs(exprs);
+
+                for name in names {
+                    self.declare(name, true);
+                }
+            }
+            Stat::LocalFunction { name, func } => {
+                self.declare(name, true);
+                self.function(func);
+            }
+            Stat::Function { name, func } => {
+                let root = &name.path[0];
+
+                if name.path.len() == 1 && name.method.is_none() {
+                    self.write(root);
+                } else if root.name == "love" && self.resolve("love").is_none() {
+                    let path: Vec<&str> = name.path.iter().map(|name| name.name.as_str()).collect();
+                    self.info.love_writes.push(path.join("."));
+                } else {
+                    self.expr(&Expr::Name(root.to_owned()));
+                }
+
+                self.function(func);
+            }
+            Stat::Assign { targets, exprs } => {
+                self.exprs(exprs);
+
+                for target in targets {
+                    match target {
+                        Expr::Name(name) => self.write(name),
+                        Expr::Member { obj, .. } => match self.get_love_path(target) {
+                            Some(path) => self.info.love_writes.push(path),
+                            None => self.expr(obj),
+                        },
+                        _ => self.expr(target),
+                    }
+                }
+            }
+            Stat::Call(expr) => self.expr(expr),
+            Stat::Do(block) => self.block(block),
+            Stat::While { cond, block } => {
+                self.expr(cond);
+                self.block(block);
+            }
+            Stat::Repeat { block, cond } => {
+                // The condition sees the locals of the block
+                self.push_scope();
+                self.stats(block);
+                self.expr(cond);
+                self.pop_scope();
+            }
+            Stat::If {
+                branches,
+                else_block,
+            } => {
+                for (cond, block) in branches {
+                    self.expr(cond);
+                    self.block(block);
+                }
+
+                if let Some(block) = else_block {
+                    self.block(block);
+                }
+            }
+            Stat::NumericFor {
+                var,
+                start,
+                limit,
+                step,
+                block,
+            } => {
+                self.expr(start);
+                self.expr(limit);
+
+                if let Some(step) = step {
+                    self.expr(step);
+                }
+
+                self.push_scope();
+                self.declare(var, false);
+                self.block(block);
+                self.pop_scope();
+            }
+            Stat::GenericFor {
+                names,
+                exprs,
+                block,
+            } => {
+                self.exprs(exprs);
+                self.push_scope();
+
+                for name in names {
+                    self.declare(name, false);
+                }
+
+                self.block(block);
+                self.pop_scope();
+            }
+            Stat::Return { exprs, .. } => self.exprs(exprs),
+            Stat::Break(_) | Stat::Goto(_) | Stat::Label(_) => {}
+        }
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) {
+        for expr in exprs {
+            self.expr(expr);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Name(name) => match self.resolve(&name.name) {
+                Some(index) => self.locals[index].used = true,
+                None => self.info.global_reads.push(name.to_owned()),
+            },
+            Expr::Member { obj, .. } => match self.get_love_path(expr) {
+                Some(path) => self.info.love_reads.push((path, expr.pos())),
+                None => self.expr(obj),
+            },
+            Expr::Index { obj, key } => {
+                self.expr(obj);
+                self.expr(key);
+            }
+            Expr::Call { func, args } => {
+                let is_require = matches!(func.as_ref(), Expr::Name(name)
+                    if name.name == "require" && self.resolve("require").is_none());
+
+                if let (true, Some(Expr::String(pos, text))) = (is_require, args.first()) {
+                    self.info.requires.push((get_string_value(text), *pos));
+                }
+
+                self.expr(func);
+                self.exprs(args);
+            }
+            Expr::MethodCall { obj, args, .. } => {
+                self.expr(obj);
+                self.exprs(args);
+            }
+            Expr::Binary { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Expr::Unary { expr, .. } | Expr::Paren(expr) => self.expr(expr),
+            Expr::Function(func) => self.function(func),
+            Expr::Table(_, fields) => {
+                for field in fields {
+                    match field {
+                        Field::Positional(value) | Field::Named(_, value) => self.expr(value),
+                        Field::Keyed(key, value) => {
+                            self.expr(key);
+                            self.expr(value);
+                        }
+                    }
+                }
+            }
+            Expr::Nil(_)
+            | Expr::True(_)
+            | Expr::False(_)
+            | Expr::Vararg(_)
+            | Expr::Number(..)
+            | Expr::String(..) => {}
+        }
+    }
+}
+
+// Collects what a file does with globals, the LOVE API and modules
+fn walk(block: &Block) -> FileInfo {
+    let mut walker = Walker::default();
+    walker.push_scope();
+    walker.block(block);
+    walker.pop_scope();
+
+    walker.info
+}
+
+// Whether a LOVE path like `love.graphics.foo` is set by the project itself
+fn is_defined_by(path: &str, love_writes: &[&String]) -> bool {
+    love_writes
+        .iter()
+        .any(|written| path == *written || path.starts_with(&format!("{}.", written)))
+}
+
+// Contents of a string literal without escape sequences, which module names don't need
+fn get_string_value(text: &str) -> String {
+    let level = text.chars().skip(1).take_while(|c| *c == '=').count();
+
+    if text.starts_with('[') {
+        return text[level + 2..text.len() - level - 2]
+            .trim_start_matches(['\r', '\n'])
+            .to_string();
+    }
+
+    text[1..text.len() - 1].to_string()
+}
+
+pub struct Linter<'a> {
+    project: &'a ProjectConfig,
+    api: ApiDatabase,
+    severities: HashMap<String, Severity>,
+    checked_version: Option<String>, // see `lock::get_checked_love_version`
+}
+
+impl<'a> Linter<'a> {
+    pub fn new(project: &'a ProjectConfig) -> Self {
+        Self::with_api(project, ApiDatabase::get())
+    }
+
+    fn with_api(project: &'a ProjectConfig, api: ApiDatabase) -> Self {
+        let mut severities: HashMap<String, Severity> = RULES
+            .iter()
+            .map(|(rule, severity)| (rule.to_string(), *severity))
+            .collect();
+
+        for (rule, severity) in &project.lint.rules {
+            if !severities.contains_key(rule) {
+                print_warn(format!("Unknown lint rule '{}' in [lint]", rule));
+                continue;
+            }
+
+            severities.insert(rule.to_owned(), *severity);
+        }
+
+        Self {
+            project,
+            api,
+            severities,
+            checked_version: None,
+        }
+    }
+
+    pub fn set_checked_version(&mut self, version: Option<String>) -> &mut Self {
+        self.checked_version = version;
+        self
+    }
+
+    fn add(&self, issues: &mut Vec<Issue>, file: &str, pos: Pos, rule: &str, message: String) {
+        let severity = self.severities[rule];
+
+        if severity == Severity::Off {
+            return;
+        }
+
+        issues.push(Issue {
+            file: file.to_string(),
+            line: pos.line,
+            column: pos.column,
+            severity,
+            rule: rule.to_string(),
+            message,
+        });
+    }
+
+    fn is_module_found(&self, module: &str) -> bool {
+        let first = module.split('.').next().unwrap_or("");
+
+        if BUILTIN_MODULES.contains(&module)
+            || BUILTIN_MODULES.contains(&first)
+            || first == "love"
+            || self.project.lua_dependencies.contains_key(first)
+        {
+            return true;
+        }
+
+        let relative = module.replace('.', "/");
+        let main = self.project.paths.get_main_dir();
+
+        if main.join(format!("{}.lua", relative)).is_file()
+            || main.join(&relative).join("init.lua").is_file()
+        {
+            return true;
+        }
+
+        let vendor = lua_deps::get_vendor_dir(self.project);
+
+        lua_deps::get_require_paths(self.project, vendor.to_str().unwrap())
+            .iter()
+            .any(|pattern| Path::new(&pattern.replace('?', &relative)).is_file())
+    }
+
+    // LOVE functions and fields that don't exist, are deprecated or behave differently in the
+    // project's LOVE version. Ones set by the scripts themselves are skipped.
+    fn add_love_api_issues(&self, issues: &mut Vec<Issue>, infos: &[(&String, FileInfo)]) {
+        let love_writes: Vec<&String> = infos
+            .iter()
+            .flat_map(|(_, info)| info.love_writes.iter())
+            .collect();
+
+        let version = self.project.package.love_version.as_deref();
+        let previous = self.checked_version.as_deref();
+
+        for (file, info) in infos {
+            for (path, pos) in &info.love_reads {
+                if is_defined_by(path, &love_writes) {
+                    continue;
+                }
+
+                let (problem, message) = match self.api.check(path, version, previous) {
+                    Some(res) => res,
+                    None => continue,
+                };
+
+                let rule = match problem {
+                    ApiProblem::Unavailable => "unknown-love-api",
+                    ApiProblem::Deprecated => "deprecated-love-api",
+                    ApiProblem::Changed => "changed-love-api",
+                };
+
+                self.add(issues, file, *pos, rule, message);
+            }
+        }
+    }
+
+    // Only checks the use of the LOVE API, for already parsed scripts
+    pub fn lint_love_api(&self, scripts: &[(String, &Block)]) -> Vec<Issue> {
+        let infos: Vec<(&String, FileInfo)> = scripts
+            .iter()
+            .map(|(file, block)| (file, walk(block)))
+            .collect();
+
+        let mut issues: Vec<Issue> = Vec::new();
+        self.add_love_api_issues(&mut issues, &infos);
+
+        issues
+    }
+
+    // Lints the given scripts together, since globals are shared between them
+    pub fn lint_files(&self, scripts: &[(String, String)]) -> Vec<Issue> {
+        let mut issues: Vec<Issue> = Vec::new();
+        let mut infos: Vec<(&String, FileInfo)> = Vec::new();
+
+        for (file, code) in scripts {
+            let (chunk, errors) = lua_parser::parse(code);
+
+            for err in errors {
+                let pos = Pos {
+                    line: err.line,
+                    column: err.column,
+                };
+
+                self.add(&mut issues, file, pos, "syntax", err.message);
+            }
+
+            infos.push((file, walk(&chunk.block)));
+        }
+
+        let config_globals = &self.project.lint.globals;
+
+        let defined: HashSet<&str> = infos
+            .iter()
+            .flat_map(|(_, info)| {
+                info.global_writes
+                    .iter()
+                    .map(|(name, _)| name.name.as_str())
+            })
+            .chain(STD_GLOBALS.iter().copied())
+            .chain(config_globals.iter().map(String::as_str))
+            .collect();
+
+        let top_level: HashSet<&str> = infos
+            .iter()
+            .flat_map(|(_, info)| info.global_writes.iter())
+            .filter(|(_, in_function)| !in_function)
+            .map(|(name, _)| name.name.as_str())
+            .chain(STD_GLOBALS.iter().copied())
+            .chain(config_globals.iter().map(String::as_str))
+            .collect();
+
+        self.add_love_api_issues(&mut issues, &infos);
+
+        for (file, info) in &infos {
+            for name in &info.global_reads {
+                if !defined.contains(name.name.as_str()) {
+                    let message = format!("Undefined global '{}'", name.name);
+                    self.add(&mut issues, file, name.pos, "undefined-global", message);
+                }
+            }
+
+            for (name, in_function) in &info.global_writes {
+                if *in_function && !top_level.contains(name.name.as_str()) {
+                    let message = format!(
+                        "Global '{}' is set inside a function. Declare it with 'local' or at the top level.",
+                        name.name
+                    );
+
+                    self.add(&mut issues, file, name.pos, "accidental-global", message);
+                }
+            }
+
+            for name in &info.unused_locals {
+                let message = format!("Unused local '{}'", name.name);
+                self.add(&mut issues, file, name.pos, "unused-local", message);
+            }
+
+            for (module, pos) in &info.requires {
+                if !self.is_module_found(module) {
+                    let message = format!("Module '{}' not found in the project", module);
+                    self.add(&mut issues, file, *pos, "unresolved-require", message);
+                }
+            }
+        }
+
+        issues.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+        issues
+    }
+
+    // Lints the project's scripts, skipping the excluded and ignored files
+    pub fn lint(&self) -> Vec<Issue> {
+        let root = self.project.paths.get_root_dir();
+
+        let scripts: Vec<(String, String)> = self
+            .project
+            .paths
+            .get_lua_files()
+            .iter()
+            .map(|path: &PathBuf| {
+                let code = fs::read_to_string(path).unwrap_or_else(|err| {
+                    exit_err(format!(
+                        "Failed to read {}: {}",
+                        path.to_str().unwrap(),
+                        err
+                    ))
+                });
+
+                let relative = files::skip_path(path, &root);
+                (relative.to_str().unwrap().replace('\\', "/"), code)
+            })
+            .collect();
+
+        self.lint_files(&scripts)
+    }
+}
+
+pub fn lint(project: &ProjectConfig, json: bool) {
+    let mut linter = Linter::new(project);
+    linter.set_checked_version(lock::get_checked_love_version());
+
+    let issues = linter.lint();
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .count();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&issues).unwrap());
+    } else {
+        for issue in &issues {
+            match issue.severity {
+                Severity::Error => print_err(issue.to_string()),
+                _ => print_warn(issue.to_string()),
+            }
+        }
+
+        if issues.is_empty() {
+            print_success("No issues found");
+        } else {
+            let summary = format!(
+                "{} errors and {} warnings found",
+                errors,
+                issues.len() - errors
+            );
+
+            println!();
+
+            match errors {
+                0 => print_warn(summary),
+                _ => print_err(summary),
+            }
+        }
+    }
+
+    if errors != 0 {
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint_code(project: &ProjectConfig, files: &[(&str, &str)]) -> Vec<String> {
+        lint_checked(project, None, files)
+    }
+
+    fn lint_checked(
+        project: &ProjectConfig,
+        checked_version: Option<&str>,
+        files: &[(&str, &str)],
+    ) -> Vec<String> {
+        let scripts: Vec<(String, String)> = files
+            .iter()
+            .map(|(file, code)| (file.to_string(), code.to_string()))
+            .collect();
+
+        Linter::with_api(project, ApiDatabase::get_embedded())
+            .set_checked_version(checked_version.map(str::to_string))
+            .lint_files(&scripts)
+            .iter()
+            .map(|issue| {
+                format!(
+                    "{}:{}:{} {}",
+                    issue.file, issue.line, issue.column, issue.rule
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rules() {
+        let mut project = ProjectConfig::new("test");
+        project.lint.globals.push("lume".to_string());
+
+        let main = "local utils = require('ffi')
+player = { x = 0 }
+
+function love.update(dt)
+    local speed, _unused = 10, 0
+    score = (score or 0) + 1
+    player.x = player.x + speed * dt
+    love.graphics.setColr(1, 1, 1)
+    lume.fn(love.update, missing)
+end
+
+function love.draw()
+    for i, v in ipairs({}) do end
+    repeat local done = true until done
+    love.graphics.print(enemy.name)
+end";
+
+        let enemy = "enemy = { name = 'bob' }\nlocal x = = 1";
+
+        assert_eq!(
+            lint_code(&project, &[("main.lua", main), ("enemy.lua", enemy)]),
+            vec![
+                "enemy.lua:2:11 syntax",
+                "main.lua:1:7 unused-local",
+                "main.lua:6:5 accidental-global",
+                "main.lua:8:5 unknown-love-api",
+                "main.lua:9:26 undefined-global",
+            ]
+        );
+    }
+
+    #[test]
+    fn versions_and_severities() {
+        let mut project = ProjectConfig::new("test");
+        project.package.love_version = Some("12.0".to_string());
+        project
+            .lint
+            .rules
+            .insert("unused-local".to_string(), Severity::Off);
+
+        let code = "local unused = 1
+love.graphics.stencil(function() end)
+love.graphics.setStencilMode('draw', 1)
+love.myHelper = function() end
+love.myHelper()";
+
+        assert_eq!(
+            lint_code(&project, &[("main.lua", code)]),
+            vec!["main.lua:2:1 unknown-love-api"]
+        );
+
+        project.package.love_version = Some("11.5".to_string());
+
+        assert_eq!(
+            lint_code(&project, &[("main.lua", code)]),
+            vec!["main.lua:3:1 unknown-love-api"]
+        );
+    }
+
+    #[test]
+    fn deprecated_and_changed() {
+        let mut project = ProjectConfig::new("test");
+        project.package.love_version = Some("12.0".to_string());
+
+        let code = "love.graphics.newText(nil)\nlove.graphics.setNewFont(12)";
+
+        assert_eq!(
+            lint_code(&project, &[("main.lua", code)]),
+            vec!["main.lua:1:1 deprecated-love-api"]
+        );
+
+        assert_eq!(
+            lint_checked(&project, Some("11.5"), &[("main.lua", code)]),
+            vec![
+                "main.lua:1:1 deprecated-love-api",
+                "main.lua:2:1 changed-love-api"
+            ]
+        );
+
+        project
+            .lint
+            .rules
+            .insert("changed-love-api".to_string(), Severity::Off);
+
+        assert_eq!(
+            lint_checked(&project, Some("11.5"), &[("main.lua", code)]),
+            vec!["main.lua:1:1 deprecated-love-api"]
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+use crate::{
+    console::{exit_err, print_err, print_step, print_success, print_warn},
+    deps::{self, Dependency},
+    project_config,
+};
+
+pub const LOCK_FILE: &str = "lover.lock";
+
+#[derive(Serialize, Deserialize)]
+pub struct Lockfile {
+    // LOVE version the code was last checked against, see `get_checked_love_version`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub love_version: Option<String>,
+
+    #[serde(default)]
+    #[serde(rename = "dependency")]
+    pub dependencies: Vec<LockedDependency>,
+
+    #[serde(default)]
+    #[serde(rename = "lua-dependency")]
+    pub lua_dependencies: Vec<LockedDependency>,
+}
+
+impl Lockfile {
+    pub fn new() -> Self {
+        Self {
+            love_version: None,
+            dependencies: Vec::new(),
+            lua_dependencies: Vec::new(),
+        }
+    }
+
+    pub fn parse_str(string: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(string)
+    }
+
+    // Entries locked for a different requested version (e.g. after changing `love_version`) don't count
+    pub fn get(&self, name: &str, version: Option<&str>) -> Option<&LockedDependency> {
+        self.dependencies
+            .iter()
+            .find(|dep| dep.name == name && dep.version.as_deref() == version)
+    }
+
+    pub fn set(&mut self, locked: LockedDependency) {
+        self.dependencies.retain(|dep| dep.name != locked.name);
+        self.dependencies.push(locked);
+        self.dependencies.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    pub fn get_lua(&self, name: &str) -> Option<&LockedDependency> {
+        self.lua_dependencies.iter().find(|dep| dep.name == name)
+    }
+
+    pub fn set_lua(&mut self, locked: LockedDependency) {
+        self.lua_dependencies.retain(|dep| dep.name != locked.name);
+        self.lua_dependencies.push(locked);
+        self.lua_dependencies.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    pub fn save(&mut self) {
+        let path = get_path();
+
+        // The first save records the version the code is checked against from then on
+        if self.love_version.is_none() {
+            self.love_version = project_config::get_love_version();
+        }
+
+        let mut text = String::from("# Auto generated by Lover. Do not edit manually.\n\n");
+
+        text += &toml::to_string_pretty(&self).unwrap_or_else(|err| {
+            exit_err(format!(
+                "Failed to serialize {}: {}. Please report a bug.",
+                LOCK_FILE, err
+            ));
+        });
+
+        fs::write(&path, text).unwrap_or_else(|err| {
+            exit_err(format!(
+                "Failed to save '{}': {}",
+                path.to_str().unwrap(),
+                err
+            ));
+        });
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LockedDependency {
+    pub name: String,
+
+    // Version requested when locking, `None` for the latest release
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+
+    pub tag: String,
+    pub url: String,
+    pub sha256: String,
+
+    // Where the dependency was declared to come from, used to notice changes in lover.toml
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl LockedDependency {
+    pub fn matches(&self, hash: &str) -> bool {
+        self.sha256.to_lowercase() == hash.to_lowercase()
+    }
+}
+
+pub fn get_path() -> PathBuf {
+    project_config::find_project_dir()
+        .unwrap_or_else(|| {
+            exit_err("Failed to find project directory");
+        })
+        .join(LOCK_FILE)
+}
+
+pub fn get() -> Option<Lockfile> {
+    let path = get_path();
+
+    if !path.exists() {
+        return None;
+    }
+
+    let string = fs::read_to_string(&path).unwrap_or_else(|err| {
+        exit_err(format!(
+            "Failed to open '{}': {}",
+            path.to_str().unwrap(),
+            err
+        ));
+    });
+
+    Some(
+        Lockfile::parse_str(&string)
+            .unwrap_or_else(|err| exit_err(format!("{} parse error: {}", LOCK_FILE, err))),
+    )
+}
+
+// LOVE version the project's code was last checked against, used to report functions that behave
+// differently after changing `love_version`. Only reads the lockfile, `lover update --love-api` records it.
+pub fn get_checked_love_version() -> Option<String> {
+    let path = project_config::find_project_dir()?.join(LOCK_FILE);
+    let string = fs::read_to_string(path).ok()?;
+
+    Lockfile::parse_str(&string).ok()?.love_version
+}
+
+// Records the installed release of each dependency in the lockfile, so it doesn't change what
+// is already installed. Dependencies without a known release are installed first.
+pub fn lock(deps: &Vec<Dependency>, lockfile: &mut Lockfile) {
+    let missing: Vec<String> = deps
+        .iter()
+        .filter(|dep| dep.get_installed_release().is_none())
+        .map(|dep| dep.get_display_name())
+        .collect();
+
+    if !missing.is_empty() {
+        if !deps::can_download() {
+            print_warn(format!("{} can't be updated in offline mode.", LOCK_FILE));
+            return;
+        }
+
+        deps::install(missing);
+    }
+
+    for dep in deps {
+        let installed = dep.get_installed_release().unwrap_or_else(|| {
+            exit_err(format!(
+                "Failed to find the installed release of '{}'",
+                dep.get_display_name()
+            ))
+        });
+
+        lockfile.set(LockedDependency {
+            name: dep.name.to_string(),
+            version: dep.version.to_owned(),
+            tag: installed.tag,
+            url: installed.url,
+            sha256: dep.get_hash(),
+            source: None,
+        });
+    }
+
+    lockfile.save();
+    print_success(format!("{} updated", LOCK_FILE));
+}
+
+// Resolves the current release of each dependency, reinstalls it if the installed file differs
+// and records it in the lockfile.
+pub fn update(deps: &Vec<Dependency>, lockfile: &mut Lockfile) {
+    if !deps::can_download() {
+        print_warn(format!("{} can't be updated in offline mode.", LOCK_FILE));
+        return;
+    }
+
+    let mut outdated: Vec<String> = Vec::new();
+
+    print_step("Resolving dependency versions...");
+
+    for dep in deps {
+        let res = dep.resolve();
+
+        let up_to_date = match &res.sha256 {
+            Some(hash) => dep.is_installed() && dep.get_hash() == *hash,
+            None => dep.is_installed() && dep.get_installed_tag().is_none_or(|tag| tag == res.tag),
+        };
+
+        if !up_to_date {
+            outdated.push(dep.get_display_name());
+        }
+    }
+
+    if !outdated.is_empty() {
+        deps::install(outdated);
+    }
+
+    lock(deps, lockfile);
+}
+
+// Makes sure the installed dependencies are the same files as the ones in the lockfile.
+// Dependencies that are not locked yet are added to it.
+pub fn verify(deps: &Vec<Dependency>, lockfile: &mut Lockfile) {
+    let mut mismatched: Vec<&LockedDependency> = Vec::new();
+    let mut unlocked: Vec<Dependency> = Vec::new();
+
+    for dep in deps {
+        match lockfile.get(dep.name, dep.version.as_deref()) {
+            Some(locked) => {
+                if !locked.matches(&dep.get_hash()) {
+                    mismatched.push(locked);
+                }
+            }
+            None => unlocked.push(deps::get_dep_or_crash(dep.get_display_name())),
+        }
+    }
+
+    if !mismatched.is_empty() {
+        print_err(format!(
+            "The following dependencies don't match {}:",
+            LOCK_FILE
+        ));
+
+        for locked in mismatched {
+            eprintln!("  {} (locked: {})", locked.name, locked.tag);
+        }
+
+        eprintln!();
+        eprintln!("Use `lover update` to update them to the latest release and lock it,");
+        eprintln!("or remove them with `lover uninstall` to reinstall the locked versions.");
+
+        std::process::exit(1);
+    }
+
+    if !unlocked.is_empty() {
+        print_warn(format!("Some dependencies are not in {} yet.", LOCK_FILE));
+        lock(&unlocked, lockfile);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked(name: &str, tag: &str) -> LockedDependency {
+        LockedDependency {
+            name: name.to_string(),
+            version: None,
+            tag: tag.to_string(),
+            url: format!("https://example.com/{}", name),
+            sha256: "ABCDEF".to_string(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn lockfile_roundtrip() {
+        let mut lockfile = Lockfile::new();
+
+        lockfile.set(locked("rcedit", "v2.0.0"));
+        lockfile.set(locked("love-win64", "11.4"));
+        lockfile.set(locked("love-win64", "11.5"));
+
+        assert_eq!(lockfile.dependencies.len(), 2);
+        assert_eq!(lockfile.dependencies[0].name, "love-win64");
+
+        let text = toml::to_string_pretty(&lockfile).unwrap();
+        let parsed = Lockfile::parse_str(&text).unwrap();
+        let dep = parsed.get("love-win64", None).unwrap();
+
+        assert_eq!(dep.tag, "11.5");
+        assert!(dep.matches("abcdef"));
+    }
+
+    #[test]
+    fn version_change() {
+        let mut lockfile = Lockfile::new();
+        let mut dep = locked("love-win64", "11.4");
+        dep.version = Some("11.4".to_string());
+
+        lockfile.set(dep);
+
+        assert!(lockfile.get("love-win64", Some("11.4")).is_some());
+        assert!(lockfile.get("love-win64", Some("11.5")).is_none());
+        assert!(lockfile.get("love-win64", None).is_none());
+
+        let mut dep = locked("love-win64", "11.5");
+        dep.version = Some("11.5".to_string());
+
+        lockfile.set(dep);
+
+        assert_eq!(lockfile.dependencies.len(), 1);
+        assert_eq!(
+            lockfile.get("love-win64", Some("11.5")).unwrap().tag,
+            "11.5"
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use serde::Deserialize;
+use std::{cmp::Ordering, collections::HashMap, fs, path::PathBuf};
+
+use crate::config;
+use crate::console::{exit_err, print_note, print_step, print_success};
+use crate::http;
+
+pub const DATABASE_URL: &str =
+    "https://raw.githubusercontent.com/Wolfyxon/lover/main/src/love_api.toml";
+
+#[derive(Deserialize, Default)]
+pub struct Change {
+    pub added: Option<String>,
+    pub removed: Option<String>,
+    pub deprecated: Option<String>,
+    pub changed: Option<String>, // behavior changed in this version
+    pub replacement: Option<String>,
+    pub note: Option<String>,
+}
+
+// Database of the LOVE API (love_api.toml), embedded or downloaded with `lover update-api`
+#[derive(Deserialize)]
+pub struct ApiDatabase {
+    #[serde(default)]
+    pub revision: u32,
+
+    pub love: Vec<String>,
+    pub callbacks: Vec<String>,
+    pub modules: HashMap<String, Vec<String>>,
+
+    #[serde(default)]
+    pub changes: HashMap<String, Change>,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum ApiProblem {
+    Unavailable, // unknown, not added yet or removed
+    Deprecated,
+    Changed, // behaves differently than in the version the code was checked against
+}
+
+#[derive(PartialEq, Debug)]
+pub enum Lookup {
+    Available,
+    Unknown,
+    Added(String),   // only available since this version
+    Removed(String), // no longer available since this version
+}
+
+// Compares versions like `11.5` and `12.0` part by part
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parse = |version: &str| -> Vec<u32> {
+        version
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+
+    parse(a).cmp(&parse(b))
+}
+
+pub fn get_path() -> PathBuf {
+    config::get_dir().join("love_api.toml")
+}
+
+// Downloads the latest database, or copies it from the given URL or path
+pub fn update(url: Option<String>) {
+    let url = url.unwrap_or(DATABASE_URL.to_string());
+
+    print_step(format!("Fetching the LOVE API database from {}", url));
+
+    let text = http::fetch_text(&url);
+
+    let db = ApiDatabase::parse(&text)
+        .unwrap_or_else(|err| exit_err(format!("Invalid LOVE API database: {}", err)));
+
+    let embedded = ApiDatabase::get_embedded();
+
+    if db.revision <= embedded.revision {
+        print_note(format!(
+            "Revision {} is not newer than the built-in revision {}, so the built-in database will still be used.",
+            db.revision, embedded.revision
+        ));
+    }
+
+    let path = get_path();
+
+    fs::create_dir_all(config::get_dir()).unwrap_or_else(|err| {
+        exit_err(format!(
+            "Failed to create the Lover data directory: {}",
+            err
+        ))
+    });
+
+    fs::write(&path, text)
+        .unwrap_or_else(|err| exit_err(format!("Failed to write {}: {}", path.display(), err)));
+
+    print_success(format!(
+        "LOVE API database updated to revision {}",
+        db.revision
+    ));
+}
+
+impl ApiDatabase {
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    pub fn get_embedded() -> Self {
+        Self::parse(include_str!("love_api.toml"))
+            .unwrap_or_else(|err| exit_err(format!("Invalid LOVE API database: {}", err)))
+    }
+
+    // Uses the downloaded database if it's newer than the embedded one
+    pub fn get() -> Self {
+        let embedded = Self::get_embedded();
+
+        let downloaded = fs::read_to_string(get_path())
+            .ok()
+            .and_then(|text| Self::parse(&text).ok());
+
+        match downloaded {
+            Some(db) if db.revision > embedded.revision => db,
+            _ => embedded,
+        }
+    }
+
+    // Gets the change entry of a path like `love.graphics.newImage`
+    pub fn get_change(&self, path: &str) -> Option<&Change> {
+        let key = path
+            .strip_prefix("love.")?
+            .splitn(3, '.')
+            .take(2)
+            .collect::<Vec<&str>>()
+            .join(".");
+
+        self.changes.get(&key)
+    }
+
+    // Suggests what to use instead of a removed or deprecated name
+    pub fn get_hint(&self, path: &str) -> String {
+        let change = match self.get_change(path) {
+            Some(change) => change,
+            None => return String::new(),
+        };
+
+        let mut hint = String::new();
+
+        if let Some(replacement) = &change.replacement {
+            hint += &format!(" Use '{}' instead.", replacement);
+        }
+
+        if let Some(note) = &change.note {
+            hint += &format!(" {}.", note);
+        }
+
+        hint
+    }
+
+    // Explains why a name can't be used in the given version or behaves differently in it.
+    // `previous` is the version the project was last checked against (see `lock::get_checked_love_version`),
+    // changed behavior is reported while the versions are on different sides of the change.
+    pub fn check(
+        &self,
+        path: &str,
+        version: Option<&str>,
+        previous: Option<&str>,
+    ) -> Option<(ApiProblem, String)> {
+        let unavailable = match self.lookup(path, version) {
+            Lookup::Available => None,
+            Lookup::Unknown => Some(format!("Unknown LOVE function or field '{}'.", path)),
+            Lookup::Added(added) => Some(format!(
+                "'{}' requires LOVE {}, but the project uses {}.",
+                path,
+                added,
+                version.unwrap_or("")
+            )),
+            Lookup::Removed(removed) => Some(format!(
+                "'{}' was removed in LOVE {}, but the project uses {}.{}",
+                path,
+                removed,
+                version.unwrap_or(""),
+                self.get_hint(path)
+            )),
+        };
+
+        if let Some(message) = unavailable {
+            return Some((ApiProblem::Unavailable, message));
+        }
+
+        let (change, version) = match (self.get_change(path), version) {
+            (Some(change), Some(version)) => (change, version),
+            _ => return None,
+        };
+
+        if let Some(deprecated) = &change.deprecated {
+            if compare_versions(version, deprecated) != Ordering::Less {
+                let message = format!(
+                    "'{}' is deprecated since LOVE {}.{}",
+                    path,
+                    deprecated,
+                    self.get_hint(path)
+                );
+
+                return Some((ApiProblem::Deprecated, message));
+            }
+        }
+
+        if let (Some(changed), Some(previous)) = (&change.changed, previous) {
+            let (low, high) = match compare_versions(previous, version) {
+                Ordering::Less => (previous, version),
+                _ => (version, previous),
+            };
+
+            if compare_versions(low, changed) == Ordering::Less
+                && compare_versions(high, changed) != Ordering::Less
+            {
+                let message = format!(
+                    "'{}' behaves differently since LOVE {}.{} Use `lover update --love-api` to record LOVE {} once the code is updated.",
+                    path,
+                    changed,
+                    change
+                        .note
+                        .as_ref()
+                        .map(|note| format!(" {}.", note))
+                        .unwrap_or_default(),
+                    version
+                );
+
+                return Some((ApiProblem::Changed, message));
+            }
+        }
+
+        None
+    }
+
+    fn check_version(&self, key: &str, version: Option<&str>) -> Lookup {
+        let (change, version) = match (self.changes.get(key), version) {
+            (Some(change), Some(version)) => (change, version),
+            _ => return Lookup::Available,
+        };
+
+        if let Some(added) = &change.added {
+            if compare_versions(version, added) == Ordering::Less {
+                return Lookup::Added(added.to_owned());
+            }
+        }
+
+        if let Some(removed) = &change.removed {
+            if compare_versions(version, removed) != Ordering::Less {
+                return Lookup::Removed(removed.to_owned());
+            }
+        }
+
+        Lookup::Available
+    }
+
+    // Looks up a path like `love.graphics.newImage`. Anything after the function is not checked.
+    // Without a version every name that exists in any version is available.
+    pub fn lookup(&self, path: &str, version: Option<&str>) -> Lookup {
+        let parts: Vec<&str> = path.split('.').collect();
+
+        if parts.len() < 2 || parts[0] != "love" {
+            return Lookup::Available;
+        }
+
+        let name = parts[1];
+
+        if let Some(functions) = self.modules.get(name) {
+            let module_lookup = self.check_version(name, version);
+
+            if parts.len() == 2 || module_lookup != Lookup::Available {
+                return module_lookup;
+            }
+
+            if !functions.iter().any(|func| func == parts[2]) {
+                return Lookup::Unknown;
+            }
+
+            return self.check_version(&format!("{}.{}", name, parts[2]), version);
+        }
+
+        if self
+            .love
+            .iter()
+            .chain(&self.callbacks)
+            .any(|known| known == name)
+        {
+            return self.check_version(name, version);
+        }
+
+        Lookup::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup() {
+        let db = ApiDatabase::get_embedded();
+
+        assert_eq!(
+            db.lookup("love.graphics.newImage", Some("11.5")),
+            Lookup::Available
+        );
+        assert_eq!(db.lookup("love.graphics.newImag", None), Lookup::Unknown);
+        assert_eq!(db.lookup("love.update", None), Lookup::Available);
+        assert_eq!(db.lookup("love.grapics", None), Lookup::Unknown);
+        assert_eq!(db.lookup("love.graphics.stencil", None), Lookup::Available);
+        assert_eq!(
+            db.lookup("love.graphics.stencil", Some("12.0")),
+            Lookup::Removed("12.0".to_string())
+        );
+        assert_eq!(
+            db.lookup("love.sensor.hasSensor", Some("11.4")),
+            Lookup::Added("12.0".to_string())
+        );
+        assert_eq!(compare_versions("11.10", "11.5"), Ordering::Greater);
+    }
+
+    #[test]
+    fn check() {
+        let db = ApiDatabase::get_embedded();
+
+        assert_eq!(
+            db.check("love.graphics.stencil", Some("12.0"), None).unwrap(),
+            (
+                ApiProblem::Unavailable,
+                "'love.graphics.stencil' was removed in LOVE 12.0, but the project uses 12.0. Use 'love.graphics.setStencilMode' instead.".to_string()
+            )
+        );
+        assert_eq!(
+            db.check("love.graphics.newText", Some("12.0"), None).unwrap(),
+            (
+                ApiProblem::Deprecated,
+                "'love.graphics.newText' is deprecated since LOVE 12.0. Use 'love.graphics.newTextBatch' instead.".to_string()
+            )
+        );
+        assert_eq!(db.check("love.graphics.newText", Some("11.5"), None), None);
+
+        // Changed behavior is only reported when the version crosses the change
+        assert_eq!(db.check("love.graphics.setColor", Some("11.0"), None), None);
+        assert_eq!(
+            db.check("love.graphics.setColor", Some("11.5"), Some("11.4")),
+            None
+        );
+        let (problem, message) = db
+            .check("love.graphics.setColor", Some("11.5"), Some("0.10.2"))
+            .unwrap();
+
+        assert_eq!(problem, ApiProblem::Changed);
+        assert!(message.contains("from 0 to 1"));
+
+        let (problem, message) = db
+            .check("love.graphics.setNewFont", Some("12.0"), Some("11.5"))
+            .unwrap();
+
+        assert_eq!(problem, ApiProblem::Changed);
+        assert!(message.contains("lover update --love-api"));
+        assert_eq!(
+            db.check("love.graphics.setNewFont", Some("12.0"), Some("12.0")),
+            None
+        );
+    }
+}
@@ -0,0 +1,22 @@
+-- Debugger bootstrap, only included by `lover run --debug`
+-- Supports the Local Lua Debugger extension for VS Code and MobDebug compatible servers (ZeroBrane Studio and others)
+
+do
+    if os.getenv("LOCAL_LUA_DEBUGGER_VSCODE") == "1" then
+        -- The game was launched by the extension, which provides lldebugger through LUA_PATH
+        require("lldebugger").start()
+    elseif os.getenv("LOVER_DEBUG") == "1" then
+        local host = os.getenv("LOVER_DEBUG_HOST") or "127.0.0.1"
+        local port = tonumber(os.getenv("LOVER_DEBUG_PORT") or "") or 8172
+        local ok, mobdebug = pcall(require, "mobdebug")
+
+        if ok then
+            -- The game keeps running if no server is listening
+            mobdebug.start(host, port)
+        else
+            print("[Lover] MobDebug not found. Add it to lover.toml:")
+            print('[lua-dependencies]')
+            print('mobdebug = { github = "pkulchenko/MobDebug", path = "src" }')
+        end
+    end
+end
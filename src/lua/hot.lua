@@ -0,0 +1,170 @@
+-- Hot reload client, only included by `lover run --hot`
+-- Lover sends `reload<TAB>module<TAB>path` lines and the modules are reloaded between frames
+
+do
+    local port = tonumber(os.getenv("LOVER_HOT_PORT") or "")
+    local root = os.getenv("LOVER_HOT_ROOT")
+    local ok, socket = pcall(require, "socket")
+    local client = port and ok and socket.connect("127.0.0.1", port)
+
+    if client and root then
+        client:settimeout(0)
+
+        -- Modules are loaded from the source directory instead of the built archive
+        local function loadSource(name)
+            local path = root .. "/" .. name:gsub("%.", "/")
+            local chunk = loadfile(path .. ".lua") or loadfile(path .. "/init.lua")
+
+            return chunk
+        end
+
+        table.insert(package.loaders or package.searchers, 2, loadSource)
+
+        -- Copies the functions of a reloaded module into the table other modules already have,
+        -- keeping the values of its other fields. `tables` maps the new tables to the old ones.
+        local function patch(old, new, tables)
+            tables[new] = old
+
+            for key, value in pairs(new) do
+                local current = rawget(old, key)
+
+                if type(value) == "table" and type(current) == "table" then
+                    if not tables[value] then
+                        patch(current, value, tables)
+                    end
+                elseif type(value) == "function" or current == nil then
+                    rawset(old, key, value)
+                end
+            end
+        end
+
+        -- The new functions refer to the new tables (e.g. `local M = {}`), they're pointed to the old ones
+        local function relink(tables)
+            for _, old in pairs(tables) do
+                for _, value in pairs(old) do
+                    if type(value) == "function" then
+                        local i = 1
+
+                        while true do
+                            local upName, upValue = debug.getupvalue(value, i)
+
+                            if not upName then
+                                break
+                            end
+
+                            if tables[upValue] then
+                                debug.setupvalue(value, i, tables[upValue])
+                            end
+
+                            i = i + 1
+                        end
+                    end
+                end
+            end
+        end
+
+        local function reload(name, path)
+            local key = package.loaded[name] ~= nil and name or name:gsub("%.", "/")
+
+            if package.loaded[key] == nil then
+                return -- not required yet
+            end
+
+            local chunk, err = loadfile(path)
+
+            if not chunk then
+                print("[Lover] " .. err)
+                return
+            end
+
+            local success, res = pcall(chunk, key)
+
+            if not success then
+                print("[Lover] Failed to reload '" .. name .. "': " .. tostring(res))
+                return
+            end
+
+            local loaded = package.loaded[key]
+
+            if type(loaded) == "table" and type(res) == "table" then
+                local tables = {}
+
+                patch(loaded, res, tables)
+                relink(tables)
+            else
+                package.loaded[key] = res == nil and true or res
+            end
+
+            print("[Lover] Reloaded '" .. name .. "'")
+        end
+
+        local buffer = ""
+
+        local function poll()
+            while client do
+                local line, err, partial = client:receive("*l")
+
+                if not line then
+                    buffer = buffer .. (partial or "")
+
+                    if err == "closed" then
+                        client = nil
+                    end
+
+                    return
+                end
+
+                local name, path = (buffer .. line):match("^reload\t([^\t]+)\t(.+)$")
+                buffer = ""
+
+                if name then
+                    reload(name, path)
+                end
+            end
+        end
+
+        -- Polls every frame of the main loop from love.run, whichever one the game ends up defining.
+        -- It's kept out of the table, so that assigning it goes through __newindex.
+        local run = rawget(love, "run")
+        rawset(love, "run", nil)
+
+        local function hotRun(...)
+            local loop = run(...)
+
+            return function(...)
+                poll()
+                return loop(...)
+            end
+        end
+
+        -- Chains the metatable that may already be set on `love`
+        local meta = getmetatable(love) or {}
+        local index, newindex = meta.__index, meta.__newindex
+
+        meta.__index = function(t, key)
+            if key == "run" then
+                return run and hotRun
+            end
+
+            if type(index) == "function" then
+                return index(t, key)
+            end
+
+            return index and index[key]
+        end
+
+        meta.__newindex = function(t, key, value)
+            if key == "run" then
+                run = value
+            elseif type(newindex) == "function" then
+                newindex(t, key, value)
+            elseif newindex then
+                newindex[key] = value
+            else
+                rawset(t, key, value)
+            end
+        end
+
+        setmetatable(love, meta)
+    end
+end
@@ -0,0 +1,9 @@
+-- Lets `require` find the Lua dependencies vendored into the `lib` directory of [paths]
+
+local loverLibPaths = "{paths}"
+
+package.path = loverLibPaths .. ";" .. package.path
+
+if love and love.filesystem and love.filesystem.setRequirePath then
+    love.filesystem.setRequirePath(love.filesystem.getRequirePath() .. ";" .. loverLibPaths)
+end
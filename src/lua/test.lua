@@ -0,0 +1,198 @@
+-- Minimal test library, only included by `lover test`
+-- Results are printed as `##lover-test<TAB>status<TAB>file<TAB>location<TAB>time<TAB>name<TAB>message` lines
+-- `loverTest` with the paths of the project and test files is defined by Lover above
+
+do
+    local tests = {}
+    local prefixes = {}
+
+    io.stdout:setvbuf("no")
+
+    local function escape(text)
+        return (tostring(text):gsub("[\\\t\n]", { ["\\"] = "\\\\", ["\t"] = "\\t", ["\n"] = "\\n" }))
+    end
+
+    local function report(status, file, location, time, name, message)
+        print(table.concat({
+            "##lover-test", status, escape(file), escape(location),
+            string.format("%.4f", time), escape(name), escape(message or "")
+        }, "\t"))
+    end
+
+    local function format(value)
+        if type(value) == "string" then
+            return string.format("%q", value)
+        end
+
+        return tostring(value)
+    end
+
+    local function equals(a, b)
+        if a == b then
+            return true
+        end
+
+        if type(a) ~= "table" or type(b) ~= "table" then
+            return false
+        end
+
+        for k, v in pairs(a) do
+            if not equals(v, b[k]) then
+                return false
+            end
+        end
+
+        for k in pairs(b) do
+            if a[k] == nil then
+                return false
+            end
+        end
+
+        return true
+    end
+
+    local function fail(message, default)
+        error(message or default, 3)
+    end
+
+    function test(name, fn)
+        local info = debug.getinfo(fn, "S")
+
+        table.insert(tests, {
+            name = table.concat(prefixes) .. name,
+            fn = fn,
+            line = info and info.linedefined
+        })
+    end
+
+    it = test
+
+    function describe(name, fn)
+        table.insert(prefixes, name .. " ")
+        fn()
+        table.remove(prefixes)
+    end
+
+    function assert_equal(actual, expected, message)
+        if not equals(actual, expected) then
+            fail(message, "expected " .. format(expected) .. ", got " .. format(actual))
+        end
+    end
+
+    function assert_not_equal(actual, unexpected, message)
+        if equals(actual, unexpected) then
+            fail(message, "expected a value other than " .. format(unexpected))
+        end
+    end
+
+    function assert_true(value, message)
+        if value ~= true then
+            fail(message, "expected true, got " .. format(value))
+        end
+    end
+
+    function assert_false(value, message)
+        if value ~= false then
+            fail(message, "expected false, got " .. format(value))
+        end
+    end
+
+    function assert_nil(value, message)
+        if value ~= nil then
+            fail(message, "expected nil, got " .. format(value))
+        end
+    end
+
+    function assert_near(actual, expected, epsilon, message)
+        if math.abs(actual - expected) > (epsilon or 1e-6) then
+            fail(message, "expected " .. format(expected) .. " +/- " .. (epsilon or 1e-6) .. ", got " .. format(actual))
+        end
+    end
+
+    function assert_error(fn, pattern, message)
+        local success, err = pcall(fn)
+
+        if success then
+            fail(message, "expected an error")
+        end
+
+        if pattern and not tostring(err):find(pattern) then
+            fail(message, "expected an error matching " .. format(pattern) .. ", got " .. format(err))
+        end
+    end
+
+    -- Splits `file:line: message` of an error
+    local function locate(err, default)
+        local location, message = tostring(err):match("^(.-:%d+): (.*)$")
+
+        if location then
+            return location, message
+        end
+
+        return default, tostring(err)
+    end
+
+    local function runFile(path, file)
+        local handle = io.open(path, "rb")
+        local chunk, err
+
+        if handle then
+            chunk, err = (loadstring or load)(handle:read("*a"), "@" .. file)
+            handle:close()
+        else
+            err = "can't open " .. path
+        end
+
+        tests = {}
+
+        local success = chunk and xpcall(chunk, function(e) err = e end)
+
+        if not success then
+            local location, message = locate(err, file)
+
+            report("error", file, location, 0, file, message)
+            return false
+        end
+
+        local passed = true
+
+        for _, t in ipairs(tests) do
+            local start = os.clock()
+            local ok, testErr = pcall(t.fn)
+            local time = os.clock() - start
+
+            local defined = file .. ":" .. (t.line or 0)
+
+            if ok then
+                report("pass", file, defined, time, t.name)
+            else
+                local location, message = locate(testErr, defined)
+
+                report("fail", file, location, time, t.name, message)
+                passed = false
+            end
+        end
+
+        return passed
+    end
+
+    function loverTest.run()
+        package.path = loverTest.main .. "/?.lua;" .. loverTest.main .. "/?/init.lua;" .. package.path
+
+        local passed = true
+
+        for _, file in ipairs(loverTest.files) do
+            passed = runFile(loverTest.root .. "/" .. file, file) and passed
+        end
+
+        return passed and 0 or 1
+    end
+end
+
+if love then
+    function love.load()
+        love.event.quit(loverTest.run())
+    end
+else
+    os.exit(loverTest.run())
+end
@@ -0,0 +1,550 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+use zip::ZipArchive;
+
+use crate::{
+    actions::CommandRunner,
+    console::{self, exit_err, print_step, print_success, print_warn, ProgressBar},
+    deps::{self, ResolvedDependency},
+    files, http,
+    lock::{self, LockedDependency, Lockfile},
+    project_config::ProjectConfig,
+};
+
+// Lua library declared in `[lua-dependencies]` of lover.toml
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LuaDependency {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub github: Option<String>, // "owner/repo"
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset: Option<String>, // pattern of a release asset, `tag` selects the release
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>, // local directory, relative to the project
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>, // only vendor this directory of the library
+}
+
+#[derive(Deserialize)]
+struct GitHubCommit {
+    sha: String,
+}
+
+impl LuaDependency {
+    pub fn validate(&self, name: &str) -> Result<(), String> {
+        let err = match (&self.github, &self.path) {
+            (Some(_), Some(_)) => Some("'github' and 'path' can't be used together"),
+            (None, None) => Some("either 'github' or 'path' is required"),
+            (None, Some(_))
+                if self.branch.is_some() || self.tag.is_some() || self.asset.is_some() =>
+            {
+                Some("'branch', 'tag' and 'asset' can only be used with 'github'")
+            }
+            (Some(repo), None) if repo.split('/').count() != 2 => {
+                Some("'github' must be in the 'owner/repo' format")
+            }
+            _ if self.branch.is_some() && (self.tag.is_some() || self.asset.is_some()) => {
+                Some("'branch' can't be used with 'tag' or 'asset'")
+            }
+            _ => None,
+        };
+
+        match err {
+            Some(err) => Err(format!("Lua dependency '{}': {}", name, err)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn get_repo(&self) -> (&str, &str) {
+        let repo = self.github.as_deref().unwrap_or_default();
+        repo.split_once('/').unwrap_or((repo, ""))
+    }
+
+    // Short description of where the library comes from, stored in the lockfile
+    pub fn get_source(&self) -> String {
+        if let Some(path) = &self.path {
+            return format!("path:{}", path);
+        }
+
+        let repo = self.github.as_deref().unwrap_or_default();
+
+        match (&self.asset, &self.tag, &self.branch) {
+            (Some(asset), Some(tag), _) => format!("github:{}@{}:{}", repo, tag, asset),
+            (Some(asset), None, _) => format!("github:{}:{}", repo, asset),
+            (None, Some(tag), _) => format!("github:{}@{}", repo, tag),
+            (None, None, Some(branch)) => format!("github:{}#{}", repo, branch),
+            (None, None, None) => format!("github:{}", repo),
+        }
+    }
+
+    pub fn resolve(&self) -> ResolvedDependency {
+        let (owner, repo) = self.get_repo();
+        let archive_url = format!("{}/{}/{}/archive", deps::get_archive_url(), owner, repo);
+
+        if let Some(pattern) = &self.asset {
+            let release = match &self.tag {
+                Some(tag) => deps::fetch_gh_tagged_release(owner, repo, tag),
+                None => deps::fetch_gh_latest_release(owner, repo),
+            };
+
+            let asset = release.get_asset_matching(pattern).unwrap_or_else(|| {
+                exit_err(format!(
+                    "No asset of {}/{} {} matches '{}'",
+                    owner, repo, release.tag_name, pattern
+                ));
+            });
+
+            return ResolvedDependency {
+                url: deps::resolve_mirror_url(&asset.browser_download_url),
+                sha256: asset.get_sha256(),
+                tag: release.tag_name,
+            };
+        }
+
+        if let Some(tag) = &self.tag {
+            return ResolvedDependency {
+                tag: tag.to_owned(),
+                url: format!("{}/refs/tags/{}.zip", archive_url, tag),
+                sha256: None,
+            };
+        }
+
+        // Branches are locked to their current commit. HEAD is the default branch.
+        let branch = self.branch.as_deref().unwrap_or("HEAD");
+        let url = format!(
+            "{}/repos/{}/{}/commits/{}",
+            deps::get_api_url(),
+            owner,
+            repo,
+            branch
+        );
+        let commit: GitHubCommit = http::fetch_struct(url);
+
+        ResolvedDependency {
+            url: format!("{}/{}.zip", archive_url, commit.sha),
+            tag: commit.sha,
+            sha256: None,
+        }
+    }
+}
+
+// Downloaded archives are cached per release or commit
+pub fn get_archive_path(dep: &LuaDependency, res: &ResolvedDependency) -> PathBuf {
+    let (owner, repo) = dep.get_repo();
+    let name = format!("{}-{}-{}-{}", owner, repo, res.tag, res.get_asset_name());
+
+    get_cache_dir().join(name.replace(['/', '\\', ':'], "_"))
+}
+
+pub fn get_vendor_dir(project: &ProjectConfig) -> PathBuf {
+    project.paths.get_temp_dir().join("lib")
+}
+
+// Modules of the game directory named like a Lua dependency, `require` may load either one
+pub fn find_shadowed(project: &ProjectConfig, src: &Path) -> Vec<String> {
+    let mut names: Vec<String> = project
+        .lua_dependencies
+        .keys()
+        .filter(|name| src.join(format!("{}.lua", name)).is_file() || src.join(name).is_dir())
+        .cloned()
+        .collect();
+
+    names.sort();
+    names
+}
+
+// Makes sure the vendored dependencies don't collide with the game's files
+pub fn check_collisions(project: &ProjectConfig, src: &Path) {
+    if project.lua_dependencies.is_empty() {
+        return;
+    }
+
+    let lib = project.paths.get_lib_dir();
+
+    if lib.is_empty() || lib.split('/').any(|part| part == "..") {
+        exit_err(format!(
+            "Invalid `lib` path '{}' in [paths]. It must be a directory inside the game.",
+            project.paths.lib
+        ));
+    }
+
+    if src.join(lib).exists() {
+        exit_err(format!(
+            "The game directory already has '{}', where the Lua dependencies are vendored. Rename it or set `lib` in [paths] of lover.toml to another directory.",
+            lib
+        ));
+    }
+
+    for name in find_shadowed(project, src) {
+        print_warn(format!(
+            "'{}' in the game directory has the same name as the Lua dependency '{}'. `require(\"{}\")` may load either one.",
+            name, name, name
+        ));
+    }
+}
+
+pub fn get_cache_dir() -> PathBuf {
+    deps::get_dir().join("lua")
+}
+
+// Paths for `require` relative to `root`. Each library can be required by its own modules too.
+pub fn get_require_paths(project: &ProjectConfig, root: &str) -> Vec<String> {
+    if project.lua_dependencies.is_empty() {
+        return Vec::new();
+    }
+
+    let mut dirs = vec![root.to_string()];
+    let mut names: Vec<&String> = project.lua_dependencies.keys().collect();
+    names.sort();
+
+    for name in names {
+        dirs.push(format!("{}/{}", root, name));
+    }
+
+    dirs.iter()
+        .flat_map(|dir| [format!("{}/?.lua", dir), format!("{}/?/init.lua", dir)])
+        .collect()
+}
+
+// Downloads the Lua dependencies of the project and extracts them into the vendor directory.
+// Locked versions are used unless the dependency is in `update`.
+pub fn vendor(project: &ProjectConfig, lockfile: &mut Lockfile, update: &[String]) {
+    let lua_deps = &project.lua_dependencies;
+
+    if lua_deps.is_empty() {
+        return;
+    }
+
+    let vendor_dir = get_vendor_dir(project);
+    let root = project.paths.get_root_dir();
+
+    let mut names: Vec<&String> = lua_deps.keys().collect();
+    names.sort();
+
+    if vendor_dir.exists() {
+        fs::remove_dir_all(&vendor_dir).unwrap_or_else(|err| {
+            exit_err(format!(
+                "Failed to clear '{}': {}",
+                vendor_dir.to_str().unwrap(),
+                err
+            ));
+        });
+    }
+
+    files::create_dir(&vendor_dir);
+    print_step("Vendoring Lua dependencies...");
+
+    let lock_before = toml::to_string(lockfile).unwrap_or_default();
+
+    lockfile
+        .lua_dependencies
+        .retain(|locked| lua_deps.contains_key(&locked.name));
+
+    for name in names {
+        let dep = &lua_deps[name];
+        dep.validate(name).unwrap_or_else(|err| exit_err(err));
+
+        let out_dir = vendor_dir.join(name);
+
+        if let Some(path) = &dep.path {
+            let mut src = root.join(path);
+
+            if let Some(dir) = &dep.dir {
+                src = src.join(dir);
+            }
+
+            copy_dir(&src, &out_dir).unwrap_or_else(|err| exit_err(err));
+            continue;
+        }
+
+        let source = dep.get_source();
+        let locked = lockfile
+            .get_lua(name)
+            .filter(|locked| locked.source.as_ref() == Some(&source))
+            .filter(|_| !update.contains(name))
+            .cloned();
+
+        let res = match &locked {
+            Some(locked) => ResolvedDependency {
+                tag: locked.tag.to_owned(),
+                url: locked.url.to_owned(),
+                sha256: Some(locked.sha256.to_owned()),
+            },
+            None if !deps::can_download() => exit_err(format!(
+                "Lua dependency '{}' is not locked and can't be downloaded in offline mode",
+                name
+            )),
+            None => dep.resolve(),
+        };
+
+        let file_name = res.get_asset_name();
+        let archive = get_archive_path(dep, &res);
+
+        let hash = fetch_archive(name, &res, &archive);
+
+        lockfile.set_lua(LockedDependency {
+            name: name.to_owned(),
+            version: None,
+            tag: res.tag.to_owned(),
+            url: res.url.to_owned(),
+            sha256: hash,
+            source: Some(source),
+        });
+
+        let extract_res = if file_name.ends_with(".zip") {
+            extract(&archive, &out_dir, dep.dir.as_deref())
+        } else {
+            // Single file libraries
+            files::create_dir(&out_dir);
+
+            fs::copy(&archive, out_dir.join(&file_name))
+                .map(|_| ())
+                .map_err(|err| format!("Failed to copy '{}': {}", file_name, err))
+        };
+
+        extract_res.unwrap_or_else(|err| {
+            exit_err(format!("Failed to vendor '{}': {}", name, err));
+        });
+    }
+
+    if toml::to_string(lockfile).unwrap_or_default() != lock_before {
+        lockfile.save();
+    }
+
+    print_success(format!("Lua dependencies vendored: {}", lua_deps.len()));
+}
+
+// Downloads the archive unless a cached copy matches. Returns its SHA256.
+fn fetch_archive(name: &str, res: &ResolvedDependency, path: &Path) -> String {
+    if path.exists() {
+        let hash = sha256::try_digest(path).unwrap_or_default();
+
+        match &res.sha256 {
+            Some(expected) if expected.eq_ignore_ascii_case(&hash) => return hash,
+            None => return hash,
+            _ => (),
+        }
+    }
+
+    if !deps::can_download() {
+        exit_err(format!(
+            "Lua dependency '{}' is not cached and can't be downloaded in offline mode",
+            name
+        ));
+    }
+
+    files::create_dir(get_cache_dir());
+
+    let mut download = res.request();
+    let mut bar = ProgressBar::new(download.len().unwrap_or(0) as usize);
+
+    bar.set_prefix(format!("{} {}", console::get_step_prefix(), name))
+        .memory_mode();
+
+    let hash = download
+        .download(path, |progress| bar.update(progress.min(bar.max)))
+        .unwrap_or_else(|err| {
+            exit_err(format!("Failed to download '{}': {}", name, err));
+        });
+
+    bar.finish();
+    hash
+}
+
+// Extracts a ZIP archive. The top directory is skipped if all files are in it, which is the case
+// for GitHub source archives. If `dir` is set, only its contents are extracted.
+pub fn extract(archive_path: &Path, out_dir: &Path, dir: Option<&str>) -> Result<(), String> {
+    let file = fs::File::open(archive_path).map_err(|err| err.to_string())?;
+    let mut archive = ZipArchive::new(file).map_err(|err| err.to_string())?;
+
+    let names: Vec<PathBuf> = (0..archive.len())
+        .filter_map(|i| archive.by_index(i).ok()?.enclosed_name())
+        .collect();
+
+    let mut prefix = get_common_dir(&names).unwrap_or_default();
+
+    if let Some(dir) = dir {
+        prefix = prefix.join(dir);
+    }
+
+    let mut extracted = 0;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|err| err.to_string())?;
+
+        let path = match entry.enclosed_name() {
+            Some(path) if entry.is_file() && path.starts_with(&prefix) => path,
+            _ => continue,
+        };
+
+        let out_path = out_dir.join(files::skip_path(&path, &prefix));
+        let mut buf: Vec<u8> = Vec::new();
+
+        entry.read_to_end(&mut buf).map_err(|err| err.to_string())?;
+        files::create_dir(out_path.parent().unwrap());
+        files::create(&out_path)
+            .write_all(&buf)
+            .map_err(|err| err.to_string())?;
+
+        extracted += 1;
+    }
+
+    if extracted == 0 {
+        return Err(format!(
+            "no files found in '{}'",
+            prefix.to_str().unwrap_or_default()
+        ));
+    }
+
+    Ok(())
+}
+
+fn get_common_dir(paths: &[PathBuf]) -> Option<PathBuf> {
+    let first = paths.first()?.components().next()?;
+
+    let common = paths
+        .iter()
+        .all(|path| path.components().count() > 1 && path.components().next() == Some(first));
+
+    match common {
+        true => Some(PathBuf::from(first.as_os_str())),
+        false => None,
+    }
+}
+
+fn copy_dir(src: &Path, dest: &Path) -> Result<(), String> {
+    if !src.is_dir() {
+        return Err(format!("'{}' is not a directory", src.to_str().unwrap()));
+    }
+
+    for path in files::get_file_tree(src) {
+        let out_path = dest.join(files::skip_path(&path, src));
+
+        files::create_dir(out_path.parent().unwrap());
+        fs::copy(&path, &out_path)
+            .map_err(|err| format!("Failed to copy '{}': {}", path.to_str().unwrap(), err))?;
+    }
+
+    Ok(())
+}
+
+// Used by the generated module to extend the require path
+pub fn gen_require_code(project: &ProjectConfig) -> String {
+    let paths = get_require_paths(project, project.paths.get_lib_dir());
+
+    if paths.is_empty() {
+        return String::new();
+    }
+
+    include_str!("lua/lib.lua").replace("{paths}", &paths.join(";"))
+}
+
+// Vendors the Lua dependencies and lets `require` find them when the game runs from source
+pub fn prepare_run(project: &ProjectConfig, cmd: &mut CommandRunner) {
+    if project.lua_dependencies.is_empty() {
+        return;
+    }
+
+    let mut lockfile = lock::get().unwrap_or_else(Lockfile::new);
+
+    check_collisions(project, &project.paths.get_main_dir());
+    vendor(project, &mut lockfile, &[]);
+    cmd.envs(&get_lua_path_env(project));
+}
+
+pub fn get_lua_path_env(project: &ProjectConfig) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    let dir = get_vendor_dir(project);
+    let paths = get_require_paths(project, dir.to_str().unwrap());
+
+    if !paths.is_empty() {
+        // `;;` is replaced with the default path
+        map.insert("LUA_PATH".to_string(), format!("{};;", paths.join(";")));
+    }
+
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_lua_deps() {
+        let project =
+            ProjectConfig::parse_str(include_str!("testData/projects/luaDeps.toml")).unwrap();
+        let deps = &project.lua_dependencies;
+
+        for (name, dep) in deps {
+            assert_eq!(dep.validate(name).is_ok(), name != "broken", "{}", name);
+        }
+
+        assert_eq!(deps["hump"].get_source(), "github:vrld/hump#master");
+        assert_eq!(deps["bump"].get_source(), "github:kikito/bump.lua@v3.1.7");
+        assert_eq!(deps["utils"].get_source(), "path:../utils");
+
+        let paths = get_require_paths(&project, project.paths.get_lib_dir());
+        assert_eq!(paths[0], "lib/?.lua");
+        assert!(paths.contains(&"lib/bump/?.lua".to_string()));
+    }
+
+    #[test]
+    fn shadowed_modules() {
+        let project =
+            ProjectConfig::parse_str(include_str!("testData/projects/luaDeps.toml")).unwrap();
+        let src = std::env::temp_dir().join("lover_test_shadowed");
+
+        let _ = fs::remove_dir_all(&src);
+        fs::create_dir_all(src.join("hump")).unwrap();
+        fs::create_dir_all(src.join("lib")).unwrap();
+        fs::write(src.join("bump.lua"), "").unwrap();
+        fs::write(src.join("main.lua"), "").unwrap();
+
+        let shadowed = find_shadowed(&project, &src);
+        fs::remove_dir_all(&src).unwrap();
+
+        assert_eq!(shadowed, vec!["bump", "hump"]);
+    }
+
+    #[test]
+    fn extract_source_archive() {
+        let temp = std::env::temp_dir().join("lover_test_lua_deps");
+        let archive_path = temp.join("hump.zip");
+
+        let _ = fs::remove_dir_all(&temp);
+        files::create_dir(&temp);
+
+        let mut zip = zip::ZipWriter::new(files::create(&archive_path));
+        let options = zip::write::SimpleFileOptions::default();
+
+        for name in ["hump-master/class.lua", "hump-master/spec/class_spec.lua"] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(b"return {}").unwrap();
+        }
+
+        zip.finish().unwrap();
+
+        extract(&archive_path, &temp.join("all"), None).unwrap();
+        assert!(temp.join("all/class.lua").exists());
+        assert!(temp.join("all/spec/class_spec.lua").exists());
+
+        extract(&archive_path, &temp.join("spec"), Some("spec")).unwrap();
+        assert!(temp.join("spec/class_spec.lua").exists());
+        assert!(extract(&archive_path, &temp.join("none"), Some("src")).is_err());
+
+        let _ = fs::remove_dir_all(&temp);
+    }
+}
@@ -0,0 +1,547 @@
+use std::fmt;
+
+// Lexer for Lua 5.1 with the LuaJIT extensions: `goto`, `::label::`, hexadecimal floats,
+// `LL`/`ULL`/`i` number suffixes and the `\x`, `\z` and `\u{}` string escapes.
+
+pub const KEYWORDS: [&str; 22] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+const SYMBOLS: [&str; 27] = [
+    "...", "..", "==", "~=", "<=", ">=", "::", "+", "-", "*", "/", "%", "^", "#", "<", ">", "=",
+    "(", ")", "{", "}", "[", "]", ";", ":", ",", ".",
+];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TokenKind {
+    Name,
+    Keyword,
+    Number,
+    String,
+    Symbol,
+    Comment,
+    Eof,
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String, // exactly as in the source
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+}
+
+impl Token {
+    // Whether the token is the keyword or symbol
+    pub fn is(&self, text: &str) -> bool {
+        matches!(self.kind, TokenKind::Keyword | TokenKind::Symbol) && self.text == text
+    }
+
+    // Text used in error messages
+    pub fn near(&self) -> String {
+        match self.kind {
+            TokenKind::Eof => "<eof>".to_string(),
+            _ => self.text.to_owned(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntaxError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    line_start: usize,
+    tokens: Vec<Token>,
+    errors: Vec<SyntaxError>,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii()
+}
+
+impl Lexer {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn is_newline(&self) -> bool {
+        matches!(self.peek(0), Some('\n') | Some('\r'))
+    }
+
+    // `\r\n` and `\n\r` count as one line break
+    fn newline(&mut self) {
+        let first = self.peek(0);
+        self.pos += 1;
+
+        if matches!(self.peek(0), Some('\n') | Some('\r')) && self.peek(0) != first {
+            self.pos += 1;
+        }
+
+        self.line += 1;
+        self.line_start = self.pos;
+    }
+
+    fn text(&self, start: usize) -> String {
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn error(&mut self, line: usize, column: usize, message: impl Into<String>) {
+        self.errors.push(SyntaxError {
+            line,
+            column,
+            message: message.into(),
+        });
+    }
+
+    fn push(&mut self, kind: TokenKind, start: usize, line: usize, column: usize) {
+        let text = self.text(start);
+
+        self.tokens.push(Token {
+            kind,
+            text,
+            line,
+            column,
+            end_line: self.line,
+        });
+    }
+
+    // Level of a long bracket at the current position (`[[` is 0, `[==[` is 2)
+    fn long_bracket_level(&self) -> Option<usize> {
+        let mut level = 0;
+
+        while self.peek(1 + level) == Some('=') {
+            level += 1;
+        }
+
+        (self.peek(1 + level) == Some('[')).then_some(level)
+    }
+
+    // Reads `[==[ ... ]==]`. Returns false if it's unfinished.
+    fn read_long_bracket(&mut self, level: usize) -> bool {
+        self.pos += level + 2;
+
+        loop {
+            match self.peek(0) {
+                None => return false,
+                Some('\n') | Some('\r') => self.newline(),
+                Some(']') => {
+                    let closes = (1..=level).all(|i| self.peek(i) == Some('='))
+                        && self.peek(level + 1) == Some(']');
+
+                    self.pos += 1;
+
+                    if closes {
+                        self.pos += level + 1;
+                        return true;
+                    }
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    fn read_comment(&mut self, start: usize, line: usize, column: usize) {
+        self.pos += 2;
+
+        if self.peek(0) == Some('[') {
+            if let Some(level) = self.long_bracket_level() {
+                if !self.read_long_bracket(level) {
+                    self.error(line, column, "unfinished long comment near '<eof>'");
+                }
+
+                self.push(TokenKind::Comment, start, line, column);
+                return;
+            }
+        }
+
+        while !self.is_newline() && self.peek(0).is_some() {
+            self.pos += 1;
+        }
+
+        self.push(TokenKind::Comment, start, line, column);
+    }
+
+    fn read_number(&mut self, start: usize, line: usize, column: usize) {
+        while let Some(c) = self.peek(0) {
+            let prev = self.chars[self.pos - 1].to_ascii_lowercase();
+            let is_exponent_sign = (c == '+' || c == '-') && (prev == 'e' || prev == 'p');
+
+            if !(is_name_char(c) || c == '.' || is_exponent_sign) {
+                break;
+            }
+
+            self.pos += 1;
+        }
+
+        let text = self.text(start);
+
+        if !is_valid_number(&text) {
+            self.error(line, column, format!("malformed number near '{}'", text));
+        }
+
+        self.push(TokenKind::Number, start, line, column);
+    }
+
+    fn read_escape(&mut self) -> Result<(), String> {
+        let start = self.pos;
+        self.pos += 1; // backslash
+
+        let invalid = |lexer: &Self| {
+            let end = (lexer.pos + 1).min(lexer.chars.len());
+            let text: String = lexer.chars[start..end].iter().collect();
+            format!("invalid escape sequence near '{}'", text)
+        };
+
+        match self.peek(0) {
+            None => Err("unfinished string near '<eof>'".to_string()),
+            Some('\n') | Some('\r') => {
+                self.newline();
+                Ok(())
+            }
+            Some('a' | 'b' | 'f' | 'n' | 'r' | 't' | 'v' | '\\' | '"' | '\'') => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some('x') => {
+                for _ in 0..2 {
+                    self.pos += 1;
+
+                    if !self.peek(0).is_some_and(|c| c.is_ascii_hexdigit()) {
+                        return Err(invalid(self));
+                    }
+                }
+
+                self.pos += 1;
+                Ok(())
+            }
+            Some('z') => {
+                self.pos += 1;
+
+                while let Some(c) = self.peek(0) {
+                    match c {
+                        '\n' | '\r' => self.newline(),
+                        c if c.is_whitespace() => self.pos += 1,
+                        _ => break,
+                    }
+                }
+
+                Ok(())
+            }
+            Some('u') => {
+                self.pos += 1;
+
+                if self.peek(0) != Some('{') {
+                    return Err(invalid(self));
+                }
+
+                let mut digits = 0;
+                self.pos += 1;
+
+                while self.peek(0).is_some_and(|c| c.is_ascii_hexdigit()) {
+                    digits += 1;
+                    self.pos += 1;
+                }
+
+                if digits == 0 || self.peek(0) != Some('}') {
+                    return Err(invalid(self));
+                }
+
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let mut value = 0;
+
+                for _ in 0..3 {
+                    match self.peek(0).and_then(|c| c.to_digit(10)) {
+                        Some(digit) => {
+                            value = value * 10 + digit;
+                            self.pos += 1;
+                        }
+                        None => break,
+                    }
+                }
+
+                if value > 255 {
+                    self.pos -= 1;
+                    return Err(invalid(self));
+                }
+
+                Ok(())
+            }
+            Some(_) => Err(invalid(self)),
+        }
+    }
+
+    fn read_string(&mut self, start: usize, line: usize, column: usize) {
+        let quote = self.peek(0);
+        self.pos += 1;
+
+        loop {
+            match self.peek(0) {
+                None => {
+                    self.error(line, column, "unfinished string near '<eof>'");
+                    break;
+                }
+                Some('\n') | Some('\r') => {
+                    let text = self.text(start);
+                    self.error(line, column, format!("unfinished string near '{}'", text));
+                    break;
+                }
+                Some('\\') => {
+                    let (escape_line, escape_column) = (self.line, self.pos - self.line_start + 1);
+
+                    if let Err(message) = self.read_escape() {
+                        self.error(escape_line, escape_column, message);
+
+                        if self.peek(0).is_some() && !self.is_newline() {
+                            self.pos += 1;
+                        }
+                    }
+                }
+                Some(c) => {
+                    self.pos += 1;
+
+                    if Some(c) == quote {
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.push(TokenKind::String, start, line, column);
+    }
+
+    fn read_symbol(&mut self, start: usize, line: usize, column: usize) {
+        for symbol in SYMBOLS {
+            let matches = symbol
+                .chars()
+                .enumerate()
+                .all(|(i, c)| self.peek(i) == Some(c));
+
+            if matches {
+                self.pos += symbol.len();
+                self.push(TokenKind::Symbol, start, line, column);
+                return;
+            }
+        }
+
+        let c = self.peek(0).unwrap();
+        self.pos += 1;
+        self.error(line, column, format!("unexpected symbol near '{}'", c));
+    }
+
+    fn run(&mut self) {
+        // Shebang line
+        if self.peek(0) == Some('#') {
+            while !self.is_newline() && self.peek(0).is_some() {
+                self.pos += 1;
+            }
+        }
+
+        while let Some(c) = self.peek(0) {
+            let start = self.pos;
+            let line = self.line;
+            let column = self.pos - self.line_start + 1;
+
+            match c {
+                '\n' | '\r' => self.newline(),
+                c if c.is_whitespace() => self.pos += 1,
+                '-' if self.peek(1) == Some('-') => self.read_comment(start, line, column),
+                '"' | '\'' => self.read_string(start, line, column),
+                '[' if self.long_bracket_level().is_some() => {
+                    let level = self.long_bracket_level().unwrap();
+
+                    if !self.read_long_bracket(level) {
+                        self.error(line, column, "unfinished long string near '<eof>'");
+                    }
+
+                    self.push(TokenKind::String, start, line, column);
+                }
+                '[' if self.peek(1) == Some('=') => {
+                    self.pos += 1;
+
+                    while self.peek(0) == Some('=') {
+                        self.pos += 1;
+                    }
+
+                    let text = self.text(start);
+                    self.error(
+                        line,
+                        column,
+                        format!("invalid long string delimiter near '{}'", text),
+                    );
+                }
+                c if c.is_ascii_digit() => self.read_number(start, line, column),
+                '.' if self.peek(1).is_some_and(|c| c.is_ascii_digit()) => {
+                    self.read_number(start, line, column)
+                }
+                c if is_name_char(c) => {
+                    while self.peek(0).is_some_and(is_name_char) {
+                        self.pos += 1;
+                    }
+
+                    let kind = match KEYWORDS.contains(&self.text(start).as_str()) {
+                        true => TokenKind::Keyword,
+                        false => TokenKind::Name,
+                    };
+
+                    self.push(kind, start, line, column);
+                }
+                _ => self.read_symbol(start, line, column),
+            }
+        }
+
+        let column = self.pos - self.line_start + 1;
+        let start = self.pos;
+        let line = self.line;
+        self.push(TokenKind::Eof, start, line, column);
+    }
+}
+
+// Decimal and hexadecimal numbers with the LuaJIT suffixes
+pub fn is_valid_number(text: &str) -> bool {
+    let lower = text.to_ascii_lowercase();
+
+    let (body, integer_only) = if let Some(body) = lower.strip_suffix("ull") {
+        (body, true)
+    } else if let Some(body) = lower.strip_suffix("ll") {
+        (body, true)
+    } else if let Some(body) = lower.strip_suffix('i') {
+        (body, false)
+    } else {
+        (lower.as_str(), false)
+    };
+
+    let (digits, exponent_char, is_digit): (&str, char, fn(&char) -> bool) =
+        match body.strip_prefix("0x") {
+            Some(hex) => (hex, 'p', char::is_ascii_hexdigit),
+            None => (body, 'e', char::is_ascii_digit),
+        };
+
+    let (mantissa, exponent) = match digits.split_once(exponent_char) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (digits, None),
+    };
+
+    if let Some(exponent) = exponent {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+
+        if integer_only || exponent.is_empty() || !exponent.chars().all(|c| c.is_ascii_digit()) {
+            return false;
+        }
+    }
+
+    let (whole, fraction) = match mantissa.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (mantissa, None),
+    };
+
+    if integer_only && fraction.is_some() {
+        return false;
+    }
+
+    let fraction = fraction.unwrap_or("");
+
+    !(whole.is_empty() && fraction.is_empty())
+        && whole.chars().all(|c| is_digit(&c))
+        && fraction.chars().all(|c| is_digit(&c))
+}
+
+// Tokens including comments, ending with `TokenKind::Eof`.
+// Invalid tokens are reported and skipped, so the rest of the code can still be checked.
+pub fn tokenize(source: &str) -> (Vec<Token>, Vec<SyntaxError>) {
+    let mut lexer = Lexer {
+        chars: source.chars().collect(),
+        pos: 0,
+        line: 1,
+        line_start: 0,
+        tokens: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    lexer.run();
+    (lexer.tokens, lexer.errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers() {
+        for valid in [
+            "3",
+            "3.0",
+            "3.",
+            ".5",
+            "3e10",
+            "3E-2",
+            "0x1F",
+            "0xA.8p1",
+            "0x.1P-4",
+            "42LL",
+            "42ull",
+            "0x7fffULL",
+            "12i",
+            "2.5i",
+        ] {
+            assert!(is_valid_number(valid), "{}", valid);
+        }
+
+        for invalid in ["3..2", "0x", "1e", "3abc", "0xg", "1.5LL", "1e5ull", "0x1p"] {
+            assert!(!is_valid_number(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn tokens() {
+        let (tokens, errors) = tokenize(
+            "local s = [==[\n]]\n]==] -- note\n::top:: goto top\nx = 'a\\z\n   b\\x41\\u{48}'",
+        );
+
+        let kinds: Vec<(TokenKind, &str, usize)> = tokens
+            .iter()
+            .map(|token| (token.kind, token.text.as_str(), token.line))
+            .collect();
+
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(kinds[3], (TokenKind::String, "[==[\n]]\n]==]", 1));
+        assert_eq!(kinds[4], (TokenKind::Comment, "-- note", 3));
+        assert_eq!(kinds[5], (TokenKind::Symbol, "::", 4));
+        assert_eq!(kinds[8], (TokenKind::Keyword, "goto", 4));
+        assert_eq!(kinds[12].1, "'a\\z\n   b\\x41\\u{48}'");
+        assert_eq!(kinds.last().unwrap().0, TokenKind::Eof);
+    }
+
+    #[test]
+    fn lexer_errors() {
+        let (_, errors) = tokenize("a = 'open\nb = 3x\nc = '\\q'\nd = [=");
+        let messages: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "1:5: unfinished string near ''open'",
+                "2:5: malformed number near '3x'",
+                "3:6: invalid escape sequence near '\\q'",
+                "4:5: invalid long string delimiter near '[='",
+            ]
+        );
+    }
+}
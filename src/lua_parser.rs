@@ -0,0 +1,1058 @@
+use std::collections::HashSet;
+
+use crate::lua_lexer::{self, SyntaxError, Token, TokenKind};
+
+// Recursive descent parser for Lua 5.1 with the LuaJIT extensions.
+// Error messages follow the ones of luac, so they look familiar.
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Pos {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Name {
+    pub name: String,
+    pub pos: Pos,
+}
+
+#[derive(Debug)]
+pub struct Block {
+    pub stats: Vec<Stat>,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct Function {
+    pub pos: Pos,
+    pub params: Vec<Name>,
+    pub vararg: bool,
+    pub block: Block,
+}
+
+#[derive(Debug)]
+pub struct FuncName {
+    pub path: Vec<Name>, // `a.b.c`
+    pub method: Option<Name>,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum Stat {
+    Local {
+        names: Vec<Name>,
+        exprs: Vec<Expr>,
+    },
+    LocalFunction {
+        name: Name,
+        func: Function,
+    },
+    Function {
+        name: FuncName,
+        func: Function,
+    },
+    Assign {
+        targets: Vec<Expr>,
+        exprs: Vec<Expr>,
+    },
+    Call(Expr),
+    Do(Block),
+    While {
+        cond: Expr,
+        block: Block,
+    },
+    Repeat {
+        block: Block,
+        cond: Expr,
+    },
+    If {
+        branches: Vec<(Expr, Block)>,
+        else_block: Option<Block>,
+    },
+    NumericFor {
+        var: Name,
+        start: Expr,
+        limit: Expr,
+        step: Option<Expr>,
+        block: Block,
+    },
+    GenericFor {
+        names: Vec<Name>,
+        exprs: Vec<Expr>,
+        block: Block,
+    },
+    Return {
+        pos: Pos,
+        exprs: Vec<Expr>,
+    },
+    Break(Pos),
+    Goto(Name),
+    Label(Name),
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum Field {
+    Positional(Expr),
+    Named(Name, Expr), // `name = value`
+    Keyed(Expr, Expr), // `[key] = value`
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum Expr {
+    Nil(Pos),
+    True(Pos),
+    False(Pos),
+    Vararg(Pos),
+    Number(Pos, String),
+    String(Pos, String), // as in the source, including the quotes
+    Function(Box<Function>),
+    Table(Pos, Vec<Field>),
+    Name(Name),
+    Member {
+        obj: Box<Expr>,
+        name: Name,
+    },
+    Index {
+        obj: Box<Expr>,
+        key: Box<Expr>,
+    },
+    Call {
+        func: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    MethodCall {
+        obj: Box<Expr>,
+        name: Name,
+        args: Vec<Expr>,
+    },
+    Binary {
+        op: String,
+        pos: Pos,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    Unary {
+        op: String,
+        pos: Pos,
+        expr: Box<Expr>,
+    },
+    Paren(Box<Expr>),
+}
+
+impl Expr {
+    pub fn pos(&self) -> Pos {
+        match self {
+            Expr::Nil(pos)
+            | Expr::True(pos)
+            | Expr::False(pos)
+            | Expr::Vararg(pos)
+            | Expr::Number(pos, _)
+            | Expr::String(pos, _)
+            | Expr::Table(pos, _) => *pos,
+            Expr::Function(func) => func.pos,
+            Expr::Name(name) => name.pos,
+            Expr::Member { obj, .. } | Expr::Index { obj, .. } | Expr::MethodCall { obj, .. } => {
+                obj.pos()
+            }
+            Expr::Call { func, .. } => func.pos(),
+            Expr::Binary { lhs, .. } => lhs.pos(),
+            Expr::Unary { pos, .. } => *pos,
+            Expr::Paren(expr) => expr.pos(),
+        }
+    }
+
+    // Dotted path like `love.graphics.newImage`, if the expression is one
+    pub fn get_path(&self) -> Option<String> {
+        match self {
+            Expr::Name(name) => Some(name.name.to_owned()),
+            Expr::Member { obj, name } => Some(format!("{}.{}", obj.get_path()?, name.name)),
+            _ => None,
+        }
+    }
+}
+
+pub struct Chunk {
+    pub block: Block,
+    pub tokens: Vec<Token>, // including comments
+}
+
+// Binary operators with their left and right priority
+fn binary_priority(token: &Token) -> Option<(u8, u8)> {
+    if !matches!(token.kind, TokenKind::Keyword | TokenKind::Symbol) {
+        return None;
+    }
+
+    match token.text.as_str() {
+        "or" => Some((1, 1)),
+        "and" => Some((2, 2)),
+        "<" | ">" | "<=" | ">=" | "~=" | "==" => Some((3, 3)),
+        ".." => Some((5, 4)), // right associative
+        "+" | "-" => Some((6, 6)),
+        "*" | "/" | "%" => Some((7, 7)),
+        "^" => Some((10, 9)), // right associative
+        _ => None,
+    }
+}
+
+const UNARY_PRIORITY: u8 = 8;
+
+fn token_pos(token: &Token) -> Pos {
+    Pos {
+        line: token.line,
+        column: token.column,
+    }
+}
+
+struct Label {
+    name: String,
+    line: usize,
+    block: usize,
+}
+
+struct Goto {
+    name: Name,
+    blocks: Vec<usize>, // the block of the goto and the blocks around it
+}
+
+// State of the function being parsed
+#[derive(Default)]
+struct FuncState {
+    vararg: bool,
+    loops: usize,
+    blocks: Vec<usize>,
+    labels: Vec<Label>,
+    gotos: Vec<Goto>,
+}
+
+type ParseResult<T> = Result<T, SyntaxError>;
+
+struct Parser {
+    tokens: Vec<Token>, // without comments
+    pos: usize,
+    errors: Vec<SyntaxError>,
+    funcs: Vec<FuncState>,
+    next_block: usize,
+}
+
+impl Parser {
+    fn current(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn peek(&self, offset: usize) -> &Token {
+        let index = (self.pos + offset).min(self.tokens.len() - 1);
+        &self.tokens[index]
+    }
+
+    fn check(&self, text: &str) -> bool {
+        self.current().is(text)
+    }
+
+    fn current_pos(&self) -> Pos {
+        token_pos(self.current())
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.current().clone();
+
+        if token.kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+
+        token
+    }
+
+    fn accept(&mut self, text: &str) -> bool {
+        if self.check(text) {
+            self.advance();
+            return true;
+        }
+
+        false
+    }
+
+    fn error_at(&self, pos: Pos, message: impl Into<String>) -> SyntaxError {
+        SyntaxError {
+            line: pos.line,
+            column: pos.column,
+            message: message.into(),
+        }
+    }
+
+    // Error at the current token
+    fn error_near(&self, message: &str) -> SyntaxError {
+        let message = format!("{} near '{}'", message, self.current().near());
+        self.error_at(self.current_pos(), message)
+    }
+
+    fn expect(&mut self, text: &str) -> ParseResult<Token> {
+        if !self.check(text) {
+            return Err(self.error_near(&format!("'{}' expected", text)));
+        }
+
+        Ok(self.advance())
+    }
+
+    // Closing token of a construct that started at `line`
+    fn expect_match(&mut self, what: &str, who: &str, line: usize) -> ParseResult<()> {
+        if self.accept(what) {
+            return Ok(());
+        }
+
+        if line == self.current().line {
+            return Err(self.error_near(&format!("'{}' expected", what)));
+        }
+
+        Err(self.error_near(&format!(
+            "'{}' expected (to close '{}' at line {})",
+            what, who, line
+        )))
+    }
+
+    fn name(&mut self) -> ParseResult<Name> {
+        if self.current().kind != TokenKind::Name {
+            return Err(self.error_near("<name> expected"));
+        }
+
+        let pos = self.current_pos();
+        let token = self.advance();
+
+        Ok(Name {
+            name: token.text,
+            pos,
+        })
+    }
+
+    // Errors which don't stop the parsing
+    fn report(&mut self, err: SyntaxError) {
+        self.errors.push(err);
+    }
+
+    fn func_state(&mut self) -> &mut FuncState {
+        self.funcs.last_mut().unwrap()
+    }
+
+    fn block_follows(&self) -> bool {
+        let token = self.current();
+
+        token.kind == TokenKind::Eof
+            || ["else", "elseif", "end", "until"]
+                .iter()
+                .any(|keyword| token.is(keyword))
+    }
+
+    // Skips to the next statement after an error
+    fn synchronize(&mut self) {
+        const STARTS: [&str; 12] = [
+            "local", "function", "if", "while", "for", "repeat", "return", "do", "goto", "break",
+            "::", ";",
+        ];
+
+        let line = self.current().line;
+        self.advance();
+
+        while !self.block_follows() {
+            let token = self.current();
+            let starts_statement = STARTS.iter().any(|keyword| token.is(keyword));
+            let starts_line = token.line != line && token.kind == TokenKind::Name;
+
+            if starts_statement || starts_line {
+                break;
+            }
+
+            self.advance();
+        }
+    }
+
+    fn block(&mut self) -> Block {
+        let id = self.next_block;
+        self.next_block += 1;
+        self.func_state().blocks.push(id);
+
+        let mut stats = Vec::new();
+
+        while !self.block_follows() {
+            let is_return = self.check("return");
+
+            match self.statement() {
+                Ok(Some(stat)) => stats.push(stat),
+                Ok(None) => {}
+                Err(err) => {
+                    self.report(err);
+                    self.synchronize();
+                    continue;
+                }
+            }
+
+            if is_return {
+                break;
+            }
+        }
+
+        self.func_state().blocks.pop();
+
+        Block { stats }
+    }
+
+    fn loop_block(&mut self) -> Block {
+        self.func_state().loops += 1;
+        let block = self.block();
+        self.func_state().loops -= 1;
+
+        block
+    }
+
+    fn statement(&mut self) -> ParseResult<Option<Stat>> {
+        let line = self.current().line;
+        let pos = self.current_pos();
+
+        if self.current().kind == TokenKind::Keyword {
+            let keyword = self.current().text.to_owned();
+
+            match keyword.as_str() {
+                "if" => return self.if_stat(line).map(Some),
+                "while" => {
+                    self.advance();
+                    let cond = self.expr()?;
+                    self.expect("do")?;
+                    let block = self.loop_block();
+                    self.expect_match("end", "while", line)?;
+
+                    return Ok(Some(Stat::While { cond, block }));
+                }
+                "do" => {
+                    self.advance();
+                    let block = self.block();
+                    self.expect_match("end", "do", line)?;
+
+                    return Ok(Some(Stat::Do(block)));
+                }
+                "for" => return self.for_stat(line).map(Some),
+                "repeat" => {
+                    self.advance();
+                    let block = self.loop_block();
+                    self.expect_match("until", "repeat", line)?;
+                    let cond = self.expr()?;
+
+                    return Ok(Some(Stat::Repeat { block, cond }));
+                }
+                "function" => {
+                    self.advance();
+                    let name = self.func_name()?;
+                    let func = self.function_body(pos, name.method.is_some(), line)?;
+
+                    return Ok(Some(Stat::Function { name, func }));
+                }
+                "local" => {
+                    self.advance();
+
+                    if self.accept("function") {
+                        let name = self.name()?;
+                        let func = self.function_body(pos, false, line)?;
+
+                        return Ok(Some(Stat::LocalFunction { name, func }));
+                    }
+
+                    let mut names = vec![self.name()?];
+
+                    while self.accept(",") {
+                        names.push(self.name()?);
+                    }
+
+                    let exprs = match self.accept("=") {
+                        true => self.expr_list()?,
+                        false => Vec::new(),
+                    };
+
+                    return Ok(Some(Stat::Local { names, exprs }));
+                }
+                "return" => {
+                    self.advance();
+
+                    let exprs = match self.block_follows() || self.check(";") {
+                        true => Vec::new(),
+                        false => self.expr_list()?,
+                    };
+
+                    self.accept(";");
+                    return Ok(Some(Stat::Return { pos, exprs }));
+                }
+                "break" => {
+                    self.advance();
+
+                    if self.func_state().loops == 0 {
+                        self.report(self.error_at(pos, "no loop to break near 'break'"));
+                    }
+
+                    return Ok(Some(Stat::Break(pos)));
+                }
+                "goto" => {
+                    self.advance();
+                    let name = self.name()?;
+                    let blocks = self.func_state().blocks.clone();
+
+                    self.func_state().gotos.push(Goto {
+                        name: name.clone(),
+                        blocks,
+                    });
+
+                    return Ok(Some(Stat::Goto(name)));
+                }
+                _ => {}
+            }
+        }
+
+        if self.accept(";") {
+            return Ok(None);
+        }
+
+        if self.accept("::") {
+            let name = self.name()?;
+            self.expect("::")?;
+            self.add_label(&name);
+
+            return Ok(Some(Stat::Label(name)));
+        }
+
+        self.expr_stat()
+    }
+
+    fn add_label(&mut self, name: &Name) {
+        let state = self.func_state();
+        let block = *state.blocks.last().unwrap();
+
+        let existing = state
+            .labels
+            .iter()
+            .find(|label| label.name == name.name && state.blocks.contains(&label.block))
+            .map(|label| label.line);
+
+        if let Some(line) = existing {
+            let message = format!("label '{}' already defined on line {}", name.name, line);
+            self.report(self.error_at(name.pos, message));
+            return;
+        }
+
+        self.func_state().labels.push(Label {
+            name: name.name.to_owned(),
+            line: name.pos.line,
+            block,
+        });
+    }
+
+    fn if_stat(&mut self, line: usize) -> ParseResult<Stat> {
+        let mut branches = Vec::new();
+        let mut else_block = None;
+
+        loop {
+            self.advance(); // `if` or `elseif`
+
+            let cond = self.expr()?;
+            self.expect("then")?;
+            branches.push((cond, self.block()));
+
+            if !self.check("elseif") {
+                break;
+            }
+        }
+
+        if self.accept("else") {
+            else_block = Some(self.block());
+        }
+
+        self.expect_match("end", "if", line)?;
+
+        Ok(Stat::If {
+            branches,
+            else_block,
+        })
+    }
+
+    fn for_stat(&mut self, line: usize) -> ParseResult<Stat> {
+        self.advance();
+        let first = self.name()?;
+
+        if self.accept("=") {
+            let start = self.expr()?;
+            self.expect(",")?;
+            let limit = self.expr()?;
+
+            let step = match self.accept(",") {
+                true => Some(self.expr()?),
+                false => None,
+            };
+
+            self.expect("do")?;
+            let block = self.loop_block();
+            self.expect_match("end", "for", line)?;
+
+            return Ok(Stat::NumericFor {
+                var: first,
+                start,
+                limit,
+                step,
+                block,
+            });
+        }
+
+        if !self.check(",") && !self.check("in") {
+            return Err(self.error_near("'=' or 'in' expected"));
+        }
+
+        let mut names = vec![first];
+
+        while self.accept(",") {
+            names.push(self.name()?);
+        }
+
+        self.expect("in")?;
+        let exprs = self.expr_list()?;
+        self.expect("do")?;
+        let block = self.loop_block();
+        self.expect_match("end", "for", line)?;
+
+        Ok(Stat::GenericFor {
+            names,
+            exprs,
+            block,
+        })
+    }
+
+    fn func_name(&mut self) -> ParseResult<FuncName> {
+        let mut path = vec![self.name()?];
+        let mut method = None;
+
+        while self.accept(".") {
+            path.push(self.name()?);
+        }
+
+        if self.accept(":") {
+            method = Some(self.name()?);
+        }
+
+        Ok(FuncName { path, method })
+    }
+
+    // Parameters and body after `function` and its name.
+    // Methods get the implicit `self` parameter.
+    fn function_body(&mut self, pos: Pos, is_method: bool, line: usize) -> ParseResult<Function> {
+        let mut params = Vec::new();
+        let mut vararg = false;
+
+        if is_method {
+            params.push(Name {
+                name: "self".to_string(),
+                pos,
+            });
+        }
+
+        self.expect("(")?;
+
+        if !self.check(")") {
+            loop {
+                if self.accept("...") {
+                    vararg = true;
+                    break;
+                }
+
+                if self.current().kind != TokenKind::Name {
+                    return Err(self.error_near("<name> expected"));
+                }
+
+                params.push(self.name()?);
+
+                if !self.accept(",") {
+                    break;
+                }
+            }
+        }
+
+        self.expect(")")?;
+
+        self.funcs.push(FuncState {
+            vararg,
+            ..Default::default()
+        });
+
+        let block = self.block();
+        let state = self.funcs.pop().unwrap();
+        self.check_gotos(state);
+
+        self.expect_match("end", "function", line)?;
+
+        Ok(Function {
+            pos,
+            params,
+            vararg,
+            block,
+        })
+    }
+
+    fn check_gotos(&mut self, state: FuncState) {
+        for goto in &state.gotos {
+            let found = state
+                .labels
+                .iter()
+                .any(|label| label.name == goto.name.name && goto.blocks.contains(&label.block));
+
+            if !found {
+                let message = format!("undefined label '{}'", goto.name.name);
+                self.report(self.error_at(goto.name.pos, message));
+            }
+        }
+    }
+
+    fn expr_stat(&mut self) -> ParseResult<Option<Stat>> {
+        let expr = self.suffixed_expr()?;
+
+        if self.check("=") || self.check(",") {
+            let mut targets = vec![expr];
+
+            while self.accept(",") {
+                targets.push(self.suffixed_expr()?);
+            }
+
+            for target in &targets {
+                if !matches!(
+                    target,
+                    Expr::Name(_) | Expr::Member { .. } | Expr::Index { .. }
+                ) {
+                    return Err(self.error_at(target.pos(), "syntax error near '='"));
+                }
+            }
+
+            self.expect("=")?;
+            let exprs = self.expr_list()?;
+
+            return Ok(Some(Stat::Assign { targets, exprs }));
+        }
+
+        if !matches!(expr, Expr::Call { .. } | Expr::MethodCall { .. }) {
+            return Err(self.error_near("syntax error"));
+        }
+
+        Ok(Some(Stat::Call(expr)))
+    }
+
+    fn expr_list(&mut self) -> ParseResult<Vec<Expr>> {
+        let mut exprs = vec![self.expr()?];
+
+        while self.accept(",") {
+            exprs.push(self.expr()?);
+        }
+
+        Ok(exprs)
+    }
+
+    fn expr(&mut self) -> ParseResult<Expr> {
+        self.sub_expr(0)
+    }
+
+    fn sub_expr(&mut self, limit: u8) -> ParseResult<Expr> {
+        let token = self.current();
+        let is_unary = token.is("not") || token.is("-") || token.is("#");
+
+        let mut expr = if is_unary {
+            let pos = self.current_pos();
+            let op = self.advance().text;
+            let expr = self.sub_expr(UNARY_PRIORITY)?;
+
+            Expr::Unary {
+                op,
+                pos,
+                expr: Box::new(expr),
+            }
+        } else {
+            self.simple_expr()?
+        };
+
+        while let Some((left, right)) = binary_priority(self.current()) {
+            if left <= limit {
+                break;
+            }
+
+            let pos = self.current_pos();
+            let op = self.advance().text;
+            let rhs = self.sub_expr(right)?;
+
+            expr = Expr::Binary {
+                op,
+                pos,
+                lhs: Box::new(expr),
+                rhs: Box::new(rhs),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn simple_expr(&mut self) -> ParseResult<Expr> {
+        let pos = self.current_pos();
+        let token = self.current().clone();
+
+        let expr = match token.kind {
+            TokenKind::Number => Expr::Number(pos, token.text),
+            TokenKind::String => Expr::String(pos, token.text),
+            TokenKind::Keyword => match token.text.as_str() {
+                "nil" => Expr::Nil(pos),
+                "true" => Expr::True(pos),
+                "false" => Expr::False(pos),
+                "function" => {
+                    self.advance();
+                    let func = self.function_body(pos, false, pos.line)?;
+
+                    return Ok(Expr::Function(Box::new(func)));
+                }
+                _ => return self.suffixed_expr(),
+            },
+            TokenKind::Symbol if token.text == "..." => {
+                if !self.func_state().vararg {
+                    self.report(
+                        self.error_at(pos, "cannot use '...' outside a vararg function near '...'"),
+                    );
+                }
+
+                Expr::Vararg(pos)
+            }
+            TokenKind::Symbol if token.text == "{" => return self.table(),
+            _ => return self.suffixed_expr(),
+        };
+
+        self.advance();
+        Ok(expr)
+    }
+
+    fn primary_expr(&mut self) -> ParseResult<Expr> {
+        if self.current().kind == TokenKind::Name {
+            return Ok(Expr::Name(self.name()?));
+        }
+
+        if self.check("(") {
+            let line = self.current().line;
+            self.advance();
+            let expr = self.expr()?;
+            self.expect_match(")", "(", line)?;
+
+            return Ok(Expr::Paren(Box::new(expr)));
+        }
+
+        Err(self.error_near("unexpected symbol"))
+    }
+
+    fn suffixed_expr(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.primary_expr()?;
+
+        loop {
+            let token = self.current();
+
+            if token.is(".") {
+                self.advance();
+                let name = self.name()?;
+
+                expr = Expr::Member {
+                    obj: Box::new(expr),
+                    name,
+                };
+            } else if token.is("[") {
+                self.advance();
+                let key = self.expr()?;
+                self.expect("]")?;
+
+                expr = Expr::Index {
+                    obj: Box::new(expr),
+                    key: Box::new(key),
+                };
+            } else if token.is(":") {
+                self.advance();
+                let name = self.name()?;
+                let args = self.call_args()?;
+
+                expr = Expr::MethodCall {
+                    obj: Box::new(expr),
+                    name,
+                    args,
+                };
+            } else if token.is("(") || token.is("{") || token.kind == TokenKind::String {
+                let args = self.call_args()?;
+
+                expr = Expr::Call {
+                    func: Box::new(expr),
+                    args,
+                };
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn call_args(&mut self) -> ParseResult<Vec<Expr>> {
+        let token = self.current().clone();
+
+        if token.kind == TokenKind::String {
+            self.advance();
+            return Ok(vec![Expr::String(token_pos(&token), token.text)]);
+        }
+
+        if token.is("{") {
+            return Ok(vec![self.table()?]);
+        }
+
+        if !token.is("(") {
+            return Err(self.error_near("function arguments expected"));
+        }
+
+        // `f\n(g)` could also be two statements
+        let previous_line = self.tokens[self.pos.saturating_sub(1)].end_line;
+
+        if token.line != previous_line {
+            return Err(self.error_at(
+                self.current_pos(),
+                "ambiguous syntax (function call x new statement) near '('",
+            ));
+        }
+
+        self.advance();
+
+        let args = match self.check(")") {
+            true => Vec::new(),
+            false => self.expr_list()?,
+        };
+
+        self.expect_match(")", "(", token.line)?;
+        Ok(args)
+    }
+
+    fn table(&mut self) -> ParseResult<Expr> {
+        let pos = self.current_pos();
+        let line = self.current().line;
+        let mut fields = Vec::new();
+
+        self.expect("{")?;
+
+        while !self.check("}") {
+            if self.check("[") {
+                self.advance();
+                let key = self.expr()?;
+                self.expect("]")?;
+                self.expect("=")?;
+                fields.push(Field::Keyed(key, self.expr()?));
+            } else if self.current().kind == TokenKind::Name && self.peek(1).is("=") {
+                let name = self.name()?;
+                self.advance();
+                fields.push(Field::Named(name, self.expr()?));
+            } else {
+                fields.push(Field::Positional(self.expr()?));
+            }
+
+            if !self.accept(",") && !self.accept(";") {
+                break;
+            }
+        }
+
+        self.expect_match("}", "{", line)?;
+        Ok(Expr::Table(pos, fields))
+    }
+}
+
+// Parses the code and returns every syntax error found, sorted by position.
+// Only the first error of a line is kept, since the others are usually caused by it.
+pub fn parse(source: &str) -> (Chunk, Vec<SyntaxError>) {
+    let (tokens, lex_errors) = lua_lexer::tokenize(source);
+
+    let mut parser = Parser {
+        tokens: tokens
+            .iter()
+            .filter(|token| token.kind != TokenKind::Comment)
+            .cloned()
+            .collect(),
+        pos: 0,
+        errors: lex_errors,
+        funcs: vec![FuncState {
+            vararg: true, // the main chunk gets the arguments of the script
+            ..Default::default()
+        }],
+        next_block: 0,
+    };
+
+    let mut block = parser.block();
+
+    while parser.current().kind != TokenKind::Eof {
+        let err = parser.error_near("'<eof>' expected");
+        parser.report(err);
+        parser.synchronize();
+
+        // The rest of the code is still checked
+        block.stats.extend(parser.block().stats);
+    }
+
+    let state = parser.funcs.pop().unwrap();
+    parser.check_gotos(state);
+
+    let mut errors = parser.errors;
+    errors.sort_by_key(|err| (err.line, err.column));
+
+    let mut lines = HashSet::new();
+    errors.retain(|err| lines.insert(err.line));
+
+    (Chunk { block, tokens }, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(source: &str) -> Vec<String> {
+        parse(source).1.iter().map(|err| err.to_string()).collect()
+    }
+
+    #[test]
+    fn valid_code() {
+        let (_, errors) = parse(include_str!("testData/lua/syntax.lua"));
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert_eq!(messages("local = 1"), vec!["1:7: <name> expected near '='"]);
+        assert_eq!(
+            messages("function f()\n  return 1\n"),
+            vec!["3:1: 'end' expected (to close 'function' at line 1) near '<eof>'"]
+        );
+        assert_eq!(
+            messages("x = 1 +\nif x then end"),
+            vec!["2:1: unexpected symbol near 'if'"]
+        );
+        assert_eq!(
+            messages("for i in pairs(t) do\n  goto skip\nend\nbreak\nprint(...)"),
+            vec![
+                "2:8: undefined label 'skip'",
+                "4:1: no loop to break near 'break'"
+            ]
+        );
+        assert_eq!(
+            messages("local function f()\n  return ...\nend\n::a:: ::a::"),
+            vec![
+                "2:10: cannot use '...' outside a vararg function near '...'",
+                "4:9: label 'a' already defined on line 4",
+            ]
+        );
+    }
+
+    #[test]
+    fn all_errors_reported() {
+        let source = "local x = = 1\nprint('ok')\nx + 1\nlocal y = 0x\nif x then\n";
+
+        assert_eq!(
+            messages(source),
+            vec![
+                "1:11: unexpected symbol near '='",
+                "3:3: syntax error near '+'",
+                "4:11: malformed number near '0x'",
+                "6:1: 'end' expected (to close 'if' at line 5) near '<eof>'",
+            ]
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use actions::CommandRunner;
-use ansi_term::Color::{Blue, Green, Yellow};
+use ansi_term::Color::{Blue, Green, Red, Yellow};
 use ansi_term::Style;
 use project_config::ProjectConfig;
 use std::env;
@@ -8,10 +8,11 @@ use std::{path::Path, process::exit};
 
 mod console;
 use console::{
-    confirm_or_exit, exit_err, get_command_line_settings, print_err, print_note, print_significant,
-    print_step, print_success, print_warn, CommandLineSettings,
+    confirm, confirm_or_exit, exit_err, get_command_line_settings, print_err, print_note,
+    print_significant, print_step, print_success, print_warn, CommandLineSettings,
 };
-use deps::DependencyInstance;
+use deps::{DependencyInstance, Integrity};
+use lock::Lockfile;
 use targets::get_targets;
 
 mod project_config;
@@ -21,11 +22,25 @@ mod appimage;
 mod config;
 mod deps;
 mod files;
+mod formatter;
+mod game_log;
+mod hot;
 mod http;
+mod instances;
+mod lint;
+mod lock;
+mod love_api;
 mod lovebrew_bundler;
+mod lua_deps;
+mod lua_lexer;
+mod lua_parser;
 mod meta;
 mod project_maker;
+mod runtime;
+mod self_update;
 mod targets;
+mod test_runner;
+mod watch;
 
 struct Command<'a> {
     alias: String,
@@ -123,6 +138,7 @@ impl<'a> CommandArg<'a> {
 struct CommandFlag<'a> {
     full: &'a str,
     //short: Option<&'a str>,
+    value: Option<&'a str>,
     description: &'a str,
 }
 
@@ -139,9 +155,38 @@ impl<'a> CommandFlag<'a> {
         CommandFlag {
             full: full,
             //short: None,
+            value: None,
             description,
         }
     }
+
+    // Flag with a value. Flags missing from `console::VALUE_FLAGS` only accept `--name=value`,
+    // which makes the value optional.
+    pub fn new_with_value(full: &'a str, value: &'a str, description: &'a str) -> Self {
+        CommandFlag {
+            full,
+            value: Some(value),
+            description,
+        }
+    }
+
+    pub fn get_usage(&self) -> String {
+        match self.value {
+            Some(value) if console::VALUE_FLAGS.contains(&self.full) => {
+                format!("--{} <{}>", self.full, value)
+            }
+            Some(value) => format!("--{}[=<{}>]", self.full, value),
+            None => format!("--{}", self.full),
+        }
+    }
+
+    // Matches `--name` and `--name=value`
+    pub fn matches(&self, arg: &str) -> bool {
+        match arg.strip_prefix("--") {
+            Some(flag) => flag.split('=').next() == Some(self.full),
+            None => false,
+        }
+    }
 }
 
 fn main() {
@@ -153,6 +198,8 @@ fn main() {
         ));
     });
 
+    self_update::remove_old_exe();
+
     let cl_settings = get_command_line_settings();
     let alias_res = cl_settings.get_command_alias();
 
@@ -217,6 +264,13 @@ fn get_commands<'a>() -> Vec<Command<'a>> {
             args: vec![],
             flags: vec![],
         },
+        Command {
+            alias: "self-update".to_string(),
+            description: "Updates Lover to the latest release.".to_string(),
+            function: cmd_self_update,
+            args: vec![],
+            flags: vec![],
+        },
         Command {
             alias: "new".to_string(),
             description: "Initializes a new Love2D project.".to_string(),
@@ -249,6 +303,52 @@ fn get_commands<'a>() -> Vec<Command<'a>> {
                     "no-prime",
                     "Doesn't run the game on the dedicated GPU if configured",
                 ),
+                CommandFlag::new_only_full("watch", "Restarts the game when a file changes"),
+                CommandFlag::new_only_full(
+                    "hot",
+                    "Runs the built game and reloads changed Lua modules without restarting it",
+                ),
+                CommandFlag::new_only_full(
+                    "debug",
+                    "Runs the built game with a debugger bootstrap (MobDebug or Local Lua Debugger)",
+                ),
+                CommandFlag::new_with_value(
+                    "target",
+                    "name",
+                    "Builds the target if needed and runs it instead of the source code",
+                ),
+                CommandFlag::new_with_value(
+                    "instances",
+                    "amount",
+                    "Runs multiple copies of the game, each with its own save directory",
+                ),
+                CommandFlag::new_with_value(
+                    "log",
+                    "file",
+                    "Saves the game's output to a file. Logs are saved in the build directory if the file is omitted",
+                ),
+                CommandFlag::new_with_value(
+                    "love",
+                    "version",
+                    "Runs the game with a LOVE version from the dependency directory",
+                ),
+            ],
+        },
+        Command {
+            alias: "test".to_string(),
+            description: "Runs the project's Lua tests.".to_string(),
+            function: cmd_test,
+            args: vec![CommandArg::opt(
+                "files...",
+                "Only runs test files containing any of these in their path.",
+            )],
+            flags: vec![
+                CommandFlag::new_with_value(
+                    "runner",
+                    "luajit|love",
+                    "Runs the tests in plain LuaJIT or headless LOVE",
+                ),
+                CommandFlag::new_with_value("junit", "file", "Saves a JUnit XML report"),
             ],
         },
         Command {
@@ -258,6 +358,26 @@ fn get_commands<'a>() -> Vec<Command<'a>> {
             args: vec![],
             flags: vec![],
         },
+        Command {
+            alias: "lint".to_string(),
+            description: "Checks Lua scripts for likely mistakes.".to_string(),
+            function: cmd_lint,
+            args: vec![],
+            flags: vec![CommandFlag::new_only_full(
+                "json",
+                "Prints the issues as JSON for editors",
+            )],
+        },
+        Command {
+            alias: "fmt".to_string(),
+            description: "Formats the project's Lua scripts.".to_string(),
+            function: cmd_fmt,
+            args: vec![],
+            flags: vec![CommandFlag::new_only_full(
+                "check",
+                "Only shows the changes and fails if any script is not formatted",
+            )],
+        },
         Command {
             alias: "build".to_string(),
             description: "Packages the game.".to_string(),
@@ -266,7 +386,10 @@ fn get_commands<'a>() -> Vec<Command<'a>> {
                 "targets...",
                 "Names of the targets to build.",
             )],
-            flags: vec![],
+            flags: vec![CommandFlag::new_only_full(
+                "offline",
+                "Fails instead of downloading missing dependencies",
+            )],
         },
         Command {
             alias: "clean".to_string(),
@@ -288,9 +411,12 @@ fn get_commands<'a>() -> Vec<Command<'a>> {
             function: cmd_dep,
             args: vec![CommandArg::opt(
                 "dependency",
-                "Name of the dependency to check",
+                "Name of the dependency to check or `prune` to delete unused files",
+            )],
+            flags: vec![CommandFlag::new_only_full(
+                "size",
+                "Shows disk usage and installed versions of dependencies",
             )],
-            flags: vec![],
         },
         Command {
             alias: "install".to_string(),
@@ -300,7 +426,10 @@ fn get_commands<'a>() -> Vec<Command<'a>> {
                 "dependencies...",
                 "Names of the dependencies to install.",
             )],
-            flags: vec![],
+            flags: vec![CommandFlag::new_only_full(
+                "offline",
+                "Only allows installing from a local mirror",
+            )],
         },
         Command {
             alias: "uninstall".to_string(),
@@ -312,11 +441,49 @@ fn get_commands<'a>() -> Vec<Command<'a>> {
             )],
             flags: vec![],
         },
+        Command {
+            alias: "outdated".to_string(),
+            description: "Lists installed dependencies that have a newer release.".to_string(),
+            function: cmd_outdated,
+            args: vec![],
+            flags: vec![],
+        },
+        Command {
+            alias: "update".to_string(),
+            description:
+                "Updates installed dependencies. Also updates the lock file inside a project."
+                    .to_string(),
+            function: cmd_update,
+            args: vec![CommandArg::opt(
+                "dependencies...",
+                "Names of the dependencies to update. All are updated if not specified.",
+            )],
+            flags: vec![CommandFlag::new_only_full(
+                "love-api",
+                "Only records the project's LOVE version as checked by `lover lint`, leaving dependencies alone",
+            )],
+        },
+        Command {
+            alias: "update-api".to_string(),
+            description: "Updates the LOVE API database used by `lover lint` and `lover parse`."
+                .to_string(),
+            function: cmd_update_api,
+            args: vec![CommandArg::opt(
+                "url",
+                "URL or path to get the database from. The latest one from the Lover repository is used if not specified.",
+            )],
+            flags: vec![],
+        },
         Command {
             alias: "fetch".to_string(),
-            description: "Fetches a dependency. Mostly for testing".to_string(),
+            description:
+                "Vendors the Lua dependencies of the project or fetches data of a dependency."
+                    .to_string(),
             function: cmd_fetch,
-            args: vec![CommandArg::req("name", "Name of the dependency.")],
+            args: vec![CommandArg::opt(
+                "name",
+                "Name of the dependency. Mostly for testing",
+            )],
             flags: vec![],
         },
         Command {
@@ -417,7 +584,7 @@ fn cmd_help(command: &Command) {
                     println!("\nFlags:");
 
                     for flag in cmd.flags {
-                        println!("  --{}: {}", flag.full, flag.description);
+                        println!("  {}: {}", flag.get_usage(), flag.description);
                     }
                 }
 
@@ -433,6 +600,11 @@ fn cmd_help(command: &Command) {
 
 fn cmd_version(_command: &Command) {
     show_version();
+    self_update::notify_if_outdated();
+}
+
+fn cmd_self_update(_command: &Command) {
+    self_update::self_update();
 }
 
 fn run_with_project(
@@ -480,6 +652,9 @@ fn run_with_project(
 
     let env = project.get_env_map(actions::Context::Run);
     cmd.envs(&env);
+    project_config::register_project();
+
+    lua_deps::prepare_run(project, cmd);
 
     if run_args.is_empty() {
         run_args.extend(project.run.default_args.to_owned());
@@ -520,20 +695,56 @@ fn run_without_project() -> PathBuf {
     main.to_path_buf()
 }
 
-fn cmd_run(_command: &Command) {
+fn cmd_run(command: &Command) {
     let conf = config::get();
     let cmd_settings = get_command_line_settings();
 
-    let project_path = project_config::find_project_config();
-    let mut cmd = conf.get_love_command();
+    let mut project = project_config::find_project_config().map(ProjectConfig::parse_file);
+
+    let debug = cmd_settings.has_flag("debug");
+
+    if debug && cmd_settings.has_flag("watch") {
+        exit_err("--debug can't be combined with --watch.");
+    }
+
+    // The debugger bootstrap can only be injected into a built game
+    let target = cmd_settings
+        .get_flag_value("target")
+        .or_else(|| (debug && !cmd_settings.has_flag("hot")).then(|| "love".to_string()));
+
+    // `love_version` of the project is ignored where LOVE binaries can't be managed
+    let love_version = match cmd_settings.get_flag_value("love") {
+        Some(version) => Some(version),
+        None => project
+            .as_ref()
+            .and_then(|project| project.package.love_version.to_owned())
+            .filter(|_| runtime::get_dep_name().is_some()),
+    };
+
+    // Other targets are run without LOVE
+    let uses_love = target.as_ref().is_none_or(|name| name == "love");
+
+    let mut cmd = match love_version.filter(|_| uses_love) {
+        Some(version) => runtime::get_love_command(&version),
+        None => conf.get_love_command(),
+    };
 
-    let mut run_args: Vec<String> = std::env::args().skip(2).into_iter().collect();
+    // Lover's own flags are not passed to the game
+    let mut run_args: Vec<String> = Vec::new();
+    let mut env_args = std::env::args().skip(2).peekable();
 
-    let main: PathBuf = match project_path {
-        Some(path) => {
-            let mut project = ProjectConfig::parse_file(path);
-            run_with_project(&mut cmd, &mut project, &mut run_args)
+    while let Some(arg) = env_args.next() {
+        match command.flags.iter().find(|flag| flag.matches(&arg)) {
+            Some(flag) if console::VALUE_FLAGS.contains(&flag.full) && !arg.contains('=') => {
+                env_args.next_if(|next| !next.starts_with("--"));
+            }
+            Some(_) => (),
+            None => run_args.push(arg),
         }
+    }
+
+    let main: PathBuf = match &mut project {
+        Some(project) => run_with_project(&mut cmd, project, &mut run_args),
 
         None => {
             print_warn("'lover.toml' not found. You're missing out on many features!");
@@ -541,30 +752,200 @@ fn cmd_run(_command: &Command) {
         }
     };
 
-    if !cmd_settings.has_flag("no-parse") {
+    if let Some(name) = &target {
+        if cmd_settings.has_flag("hot") || cmd_settings.has_flag("watch") {
+            exit_err("--target can't be combined with --hot or --watch.");
+        }
+
+        let project = project
+            .as_ref()
+            .unwrap_or_else(|| exit_err("--target can only be used in a project."));
+
+        cmd = get_target_runner(name, project, cmd, debug);
+    } else if !cmd_settings.has_flag("no-parse") {
         actions::parse_all(&main);
     }
 
+    if debug {
+        print_note(format!(
+            "Debugging on {}:{}. Start a MobDebug server there before the game, e.g. Project > Start Debugger Server in ZeroBrane Studio.",
+            conf.run.debug_host, conf.run.debug_port
+        ));
+    }
+
     if (conf.run.prime || cmd_settings.has_flag("prime")) && !cmd_settings.has_flag("no-prime") {
         cmd.prime();
     }
 
-    cmd.add_arg(main.to_str().unwrap());
+    let instance_count = cmd_settings.has_flag("instances").then(|| {
+        let value = cmd_settings
+            .get_flag_value("instances")
+            .unwrap_or_else(|| exit_err("--instances requires the amount of instances."));
+
+        if cmd_settings.has_flag("hot") || cmd_settings.has_flag("watch") {
+            exit_err("--instances can't be combined with --hot or --watch.");
+        }
+
+        instances::parse_count(&value)
+    });
+
+    let log_enabled = cmd_settings.has_flag("log") || conf.run.log;
+
+    let log = if cmd_settings.has_flag("hot") || cmd_settings.has_flag("watch") {
+        if log_enabled {
+            print_warn("The game's output is not logged with --hot or --watch.");
+        }
+
+        None
+    } else {
+        log_enabled.then(|| {
+            game_log::open(
+                cmd_settings.get_flag_value("log"),
+                project.as_ref(),
+                conf.run.max_logs,
+            )
+        })
+    };
+
+    if cmd_settings.has_flag("hot") {
+        let project = project.unwrap_or_else(|| exit_err("--hot can only be used in a project."));
+
+        hot::run(&mut cmd, &project, run_args, debug);
+        return;
+    }
+
+    if target.is_none() {
+        cmd.add_arg(main.to_str().unwrap());
+    }
+
     cmd.add_args(run_args);
 
-    cmd.run();
+    if let Some(count) = instance_count {
+        match &project {
+            Some(project) => instances::run(
+                &cmd,
+                count,
+                &project.run,
+                project.paths.get_temp_dir().join("instances"),
+                log.as_ref(),
+            ),
+            None => instances::run(
+                &cmd,
+                count,
+                &project_config::Run::default(),
+                env::temp_dir().join("lover_instances"),
+                log.as_ref(),
+            ),
+        }
+
+        return;
+    }
+
+    if cmd_settings.has_flag("watch") {
+        let paths = project.as_ref().map(|project| &project.paths);
+        watch::run(&cmd, &main, paths, None, || ());
+        return;
+    }
+
+    match &log {
+        Some(log) => {
+            if !game_log::run(&cmd, log) {
+                exit(1);
+            }
+        }
+        None => {
+            cmd.run();
+        }
+    }
+}
+
+// Builds the target if its artifact is missing or older than the project files.
+// With `debug` the game is built into the temp directory with the debugger bootstrap instead.
+fn get_target_runner(
+    name: &str,
+    project: &ProjectConfig,
+    love: CommandRunner,
+    debug: bool,
+) -> CommandRunner {
+    targets::get_target_or_crash(name);
+
+    let artifact = if debug {
+        if name != "love" {
+            exit_err("--debug can only be used with the love target.");
+        }
+
+        let options = targets::ModuleOptions {
+            hot: false,
+            debug: true,
+        };
+
+        targets::build_run_love(project, &options)
+    } else {
+        let artifact = targets::get_artifact(name, project)
+            .unwrap_or_else(|| exit_err(format!("Target '{}' can't be run.", name)));
+
+        if targets::is_outdated(&artifact, project) {
+            build_targets(vec![name.to_string()]);
+        } else {
+            print_note(format!("'{}' is up to date.", name));
+        }
+
+        artifact
+    };
+
+    let mut cmd = match name {
+        "love" => {
+            let mut cmd = love;
+            cmd.add_arg(artifact.to_str().unwrap());
+            cmd
+        }
+        "win64" | "win32" => CommandRunner::new(artifact.to_str().unwrap()).to_wine(),
+        _ => CommandRunner::new(artifact.to_str().unwrap()),
+    };
+
+    cmd.envs(&project.get_env_map(actions::Context::Run));
+    print_significant(
+        "Running",
+        files::skip_path_string(&artifact, project.paths.get_root_dir()),
+    );
+
+    cmd
+}
+
+fn cmd_test(command: &Command) {
+    let cmd_settings = get_command_line_settings();
+    let project = project_config::get();
+
+    let runner = cmd_settings
+        .get_flag_value("runner")
+        .map(|name| test_runner::Runner::parse(&name));
+
+    test_runner::test(
+        &project,
+        &command.get_args(),
+        runner,
+        cmd_settings.get_flag_value("junit"),
+    );
 }
 
 fn cmd_parse(_command: &Command) {
     let src = project_config::get().paths.get_main_dir();
 
-    actions::get_parser().unwrap_or_else(|| {
-        exit_err(format!("Cannot parse. Luac Lua parser not found."));
-    });
-
     actions::parse_all(&src);
 }
 
+fn cmd_lint(_command: &Command) {
+    let project = project_config::get();
+
+    lint::lint(&project, get_command_line_settings().has_flag("json"));
+}
+
+fn cmd_fmt(_command: &Command) {
+    let project = project_config::get();
+
+    formatter::format_project(&project, get_command_line_settings().has_flag("check"));
+}
+
 fn cmd_build(command: &Command) {
     let project_conf = project_config::get();
     let mut target_names = project_conf.build.get_default_targets();
@@ -575,26 +956,49 @@ fn cmd_build(command: &Command) {
         target_names = args;
     }
 
+    build_targets(target_names);
+}
+
+fn build_targets(target_names: Vec<String>) {
+    let project_conf = project_config::get();
     let targets = targets::get_targets_by_strings(target_names.to_owned());
+    let lockfile = lock::get();
+    let mut dep_names: Vec<String> = Vec::new();
     let mut to_install: Vec<String> = Vec::new();
 
     print_significant("Initializing build of", target_names.join(", "));
+    project_config::register_project();
 
     for target in &targets {
         for dep in target.get_all_deps() {
-            if !dep.is_installed() && !to_install.contains(&dep.name.to_string()) {
-                to_install.push(dep.name.to_string());
+            let name = dep.get_display_name();
+
+            if !dep_names.contains(&name) {
+                dep_names.push(name.to_owned());
+            }
+
+            if !dep.is_installed() && !to_install.contains(&name) {
+                to_install.push(name);
             }
         }
     }
 
     if to_install.len() != 0 {
         print_warn("Some dependencies are missing and must be installed.".to_string());
-        deps::install(to_install);
+        deps::install_deps(deps::get_deps_by_strings(to_install), lockfile.as_ref());
     } else {
         print_success("All dependencies are installed.".to_string());
     }
 
+    if !dep_names.is_empty() {
+        let used_deps = deps::get_deps_by_strings(dep_names);
+
+        match lockfile {
+            Some(mut lockfile) => lock::verify(&used_deps, &mut lockfile),
+            None => lock::lock(&used_deps, &mut Lockfile::new()),
+        }
+    }
+
     files::create_dir(&project_conf.paths.get_temp_dir());
 
     println!();
@@ -700,14 +1104,84 @@ fn cmd_target(command: &Command) {
     }
 }
 
+fn show_dep_sizes() {
+    print_significant("Disk usage of dependencies", "\n");
+
+    let mut total: u64 = 0;
+
+    for dep in deps::get_deps() {
+        let versions = dep.get_installed_versions();
+
+        if versions.is_empty() {
+            continue;
+        }
+
+        let size: u64 = versions.iter().map(|ver| ver.size).sum();
+        total += size;
+
+        println!(
+            "- {}: {:.2} MB",
+            Style::new().fg(Green).paint(dep.name),
+            deps::to_mb(size)
+        );
+
+        for ver in versions {
+            let pinned = if ver.pinned { " (pinned)" } else { "" };
+
+            println!(
+                "    {}{}: {:.2} MB",
+                ver.version,
+                pinned,
+                deps::to_mb(ver.size)
+            );
+        }
+    }
+
+    let lua_cache = lua_deps::get_cache_dir();
+
+    if lua_cache.exists() {
+        let size: u64 = files::get_file_tree(&lua_cache)
+            .iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .map(|meta| meta.len())
+            .sum();
+        total += size;
+
+        println!("- Lua dependency cache: {:.2} MB", deps::to_mb(size));
+    }
+
+    println!("\nTotal: {:.2} MB", deps::to_mb(total));
+    println!("`lover dep prune` to delete unused files.");
+}
+
 fn cmd_dep(command: &Command) {
+    if get_command_line_settings().has_flag("size") {
+        show_dep_sizes();
+        return;
+    }
+
     match command.get_arg("dependency") {
+        Some(name) if name == "prune" => deps::prune(),
         Some(name) => {
             let dep = deps::get_dep_or_crash(name.as_str());
             let mut status = "not installed";
+            let mut integrity_err: Option<String> = None;
 
             if dep.is_installed() {
-                status = "installed";
+                match dep.verify(true) {
+                    Integrity::Valid => status = "installed",
+                    Integrity::Unverified => {
+                        status = "unverified";
+                        integrity_err = Some(format!(
+                            "'{}' was installed by an older Lover version and can't be verified. It may be incomplete.",
+                            dep.get_display_name()
+                        ));
+                    }
+                    Integrity::Corrupted(err) => {
+                        status = "corrupted";
+                        integrity_err = Some(err);
+                    }
+                }
             }
 
             print_significant("Details of", dep.name);
@@ -715,32 +1189,92 @@ fn cmd_dep(command: &Command) {
             println!("{}\n", Style::new().italic().paint(dep.description));
 
             println!("Status: {}", status);
+            println!(
+                "Version: {}",
+                dep.version.to_owned().unwrap_or("latest".to_string())
+            );
             println!("Location: {}", dep.get_path().to_str().unwrap());
             println!("Repository: {}", dep.get_repo_url());
 
             println!();
             print_step("Actions:");
 
-            println!("`lover install {}` to install or update.", dep.name);
-            println!("`lover uninstall {}` to remove.", dep.name);
+            println!(
+                "`lover install {}` to install or update.",
+                dep.get_display_name()
+            );
+            println!("`lover uninstall {}` to remove.", dep.get_display_name());
+
+            if let Some(err) = integrity_err {
+                println!();
+                print_warn(err);
+
+                if confirm("Reinstall it now?") {
+                    println!();
+                    deps::install(vec![dep.get_display_name()]);
+                }
+            }
         }
         None => {
             print_significant("Available dependencies", "\n");
 
             let installed_style = Style::new().fg(Green);
+            let unverified_style = Style::new().fg(Yellow);
+            let corrupted_style = Style::new().fg(Red);
+            let mut corrupted: Vec<String> = Vec::new();
+            let mut unverified: Vec<String> = Vec::new();
 
             for dep in deps::get_deps() {
                 let mut styled_name = dep.name.to_string();
                 let mut suffix = "";
 
                 if dep.is_installed() {
-                    styled_name = installed_style.paint(styled_name).to_string();
-                    suffix = "(installed)";
+                    match dep.verify(false) {
+                        Integrity::Valid => {
+                            styled_name = installed_style.paint(styled_name).to_string();
+                            suffix = "(installed)";
+                        }
+                        Integrity::Unverified => {
+                            styled_name = unverified_style.paint(styled_name).to_string();
+                            suffix = "(unverified)";
+                            unverified.push(dep.get_display_name());
+                        }
+                        Integrity::Corrupted(_) => {
+                            styled_name = corrupted_style.paint(styled_name).to_string();
+                            suffix = "(corrupted)";
+                            corrupted.push(dep.get_display_name());
+                        }
+                    }
                 }
 
                 println!("- {} {}: {}", styled_name, suffix, dep.description);
             }
 
+            if !corrupted.is_empty() {
+                println!();
+                print_warn(format!(
+                    "Some dependencies are corrupted: {}",
+                    corrupted.join(", ")
+                ));
+            }
+
+            if !unverified.is_empty() {
+                println!();
+                print_warn(format!(
+                    "Some dependencies were installed by an older Lover version and can't be verified: {}",
+                    unverified.join(", ")
+                ));
+            }
+
+            let broken = !corrupted.is_empty() || !unverified.is_empty();
+
+            if broken && confirm("Reinstall them now?") {
+                println!();
+                corrupted.append(&mut unverified);
+                deps::install(corrupted);
+                return;
+            }
+
             println!(
                 "\nDependencies are located in: {}\n",
                 deps::get_dir().to_str().unwrap()
@@ -764,7 +1298,10 @@ fn cmd_uninstall(command: &Command) {
         if dep.is_installed() {
             amt += 1;
         } else {
-            print_warn(format!("'{}' is not installed, ignoring.", dep.name));
+            print_warn(format!(
+                "'{}' is not installed, ignoring.",
+                dep.get_display_name()
+            ));
         }
     }
 
@@ -776,7 +1313,7 @@ fn cmd_uninstall(command: &Command) {
 
     for dep in &dependencies {
         if dep.is_installed() {
-            println!("  {}", dep.name);
+            println!("  {}", dep.get_display_name());
         }
     }
 
@@ -786,15 +1323,8 @@ fn cmd_uninstall(command: &Command) {
 
     for dep in &dependencies {
         if dep.is_installed() {
-            let path = dep.get_path();
-            let res = std::fs::remove_file(&path);
-
-            if res.is_err() {
-                print_err(format!(
-                    "Failed to delete '{}': {}",
-                    &path.to_str().unwrap(),
-                    res.err().unwrap()
-                ));
+            if let Err(err) = dep.uninstall() {
+                print_err(err);
                 fail = true;
             }
         }
@@ -807,8 +1337,142 @@ fn cmd_uninstall(command: &Command) {
     }
 }
 
+fn cmd_outdated(_command: &Command) {
+    print_step("Checking for updates...");
+    let outdated = deps::get_outdated(deps::get_installed_deps());
+
+    if outdated.is_empty() {
+        print_success("All dependencies are up to date.");
+        return;
+    }
+
+    print_significant("Outdated dependencies", "\n");
+
+    for out in &outdated {
+        let installed = out.installed.to_owned().unwrap_or("unknown".to_string());
+
+        println!(
+            "  {}: {} -> {}",
+            out.dep.get_display_name(),
+            installed,
+            Style::new().fg(Green).paint(&out.latest)
+        );
+    }
+
+    println!("\n`lover update` to update all of them.");
+    println!("`lover update <name>` to update a specific one.");
+}
+
+fn cmd_update_api(command: &Command) {
+    love_api::update(command.get_args().first().cloned());
+}
+
+fn cmd_update(command: &Command) {
+    let mut names = command.get_args();
+
+    // Changed LOVE functions are no longer reported once the code is updated for the current version
+    if get_command_line_settings().has_flag("love-api") {
+        let version = project_config::get()
+            .package
+            .love_version
+            .to_owned()
+            .unwrap_or_else(|| exit_err("The project doesn't set `love_version`."));
+
+        let mut lockfile = lock::get().unwrap_or_else(Lockfile::new);
+        lockfile.love_version = Some(version.to_owned());
+        lockfile.save();
+
+        print_success(format!("Recorded LOVE {} in {}", version, lock::LOCK_FILE));
+        return;
+    }
+
+    if project_config::find_project_dir().is_none() {
+        deps::update(names);
+        return;
+    }
+
+    let project_conf = project_config::get();
+    let mut lockfile = lock::get().unwrap_or_else(Lockfile::new);
+
+    // Lua dependencies are vendored separately
+    let explicit = !names.is_empty();
+    let mut lua_names: Vec<String> = project_conf
+        .lua_dependencies
+        .keys()
+        .filter(|name| !explicit || names.contains(name))
+        .cloned()
+        .collect();
+
+    lua_names.sort();
+    names.retain(|name| !lua_names.contains(name));
+
+    if !explicit {
+        names = lockfile
+            .dependencies
+            .iter()
+            .map(|locked| locked.name.to_owned())
+            .collect();
+    }
+
+    if !explicit && names.is_empty() {
+        let default_targets =
+            targets::get_targets_by_strings(project_conf.build.get_default_targets());
+
+        for target in default_targets {
+            for name in target.get_all_dep_names() {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+    }
+
+    // Other installed dependencies are updated too, since `lover outdated` lists them
+    let others: Vec<String> = match explicit {
+        true => Vec::new(),
+        false => deps::get_installed_deps()
+            .iter()
+            .map(|dep| dep.name.to_string())
+            .filter(|name| !names.contains(name))
+            .collect(),
+    };
+
+    if names.is_empty() && lua_names.is_empty() && others.is_empty() {
+        print_success("Nothing to update.");
+        return;
+    }
+
+    if !names.is_empty() {
+        print_significant("Updating", names.join(", "));
+        lock::update(&deps::get_deps_by_strings(names), &mut lockfile);
+    }
+
+    if !others.is_empty() {
+        deps::update(others);
+    }
+
+    if !lua_names.is_empty() {
+        print_significant("Updating Lua dependencies", lua_names.join(", "));
+        lua_deps::vendor(&project_conf, &mut lockfile, &lua_names);
+    }
+}
+
 fn cmd_fetch(command: &Command) {
-    let name = command.get_arg("name").unwrap();
+    let name = match command.get_arg("name") {
+        Some(name) => name,
+        None => {
+            let project = project_config::get();
+
+            if project.lua_dependencies.is_empty() {
+                print_success("The project has no Lua dependencies.");
+                return;
+            }
+
+            let mut lockfile = lock::get().unwrap_or_else(Lockfile::new);
+            lua_deps::vendor(&project, &mut lockfile, &[]);
+            return;
+        }
+    };
     let dep = deps::get_dep_or_crash(&name);
 
     print_significant("Data of dependency", name.to_owned());
@@ -851,5 +1515,10 @@ fn cmd_env(_command: &Command) {
 }
 
 fn cmd_module(_command: &Command) {
-    println!("{}", targets::gen_module());
+    let project = project_config::get();
+
+    println!(
+        "{}",
+        targets::gen_module(&project, &targets::ModuleOptions::default())
+    );
 }
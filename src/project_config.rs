@@ -1,9 +1,14 @@
 use crate::{
     actions::Context,
-    console::{exit_err, print_warn},
+    config,
+    console::{self, exit_err, print_warn},
+    deps::CustomDependency,
     files,
+    formatter::{IndentStyle, QuoteStyle},
+    lint::Severity,
+    lua_deps::LuaDependency,
     meta::ProjectMeta,
-    targets,
+    targets::{self, Arch},
 };
 use globset::{Glob, GlobSetBuilder};
 use serde::{Deserialize, Serialize};
@@ -12,7 +17,8 @@ use std::{
     env,
     fs::File,
     io::Read,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::OnceLock,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
@@ -39,6 +45,27 @@ pub struct ProjectConfig {
     #[serde(skip_serializing_if = "Env::is_default")]
     pub env: Env,
 
+    #[serde(default = "Targets::default")]
+    #[serde(skip_serializing_if = "Targets::is_default")]
+    pub targets: Targets,
+
+    #[serde(default = "Lint::default")]
+    #[serde(skip_serializing_if = "Lint::is_default")]
+    pub lint: Lint,
+
+    #[serde(default = "Fmt::default")]
+    #[serde(skip_serializing_if = "Fmt::is_default")]
+    pub fmt: Fmt,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub dependencies: HashMap<String, CustomDependency>,
+
+    #[serde(default)]
+    #[serde(rename = "lua-dependencies")]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub lua_dependencies: HashMap<String, LuaDependency>,
+
     directories: Option<Paths>, // old 'paths'
 }
 
@@ -51,6 +78,11 @@ impl ProjectConfig {
             build: Build::default(),
             run: Run::default(),
             env: Env::default(),
+            targets: Targets::default(),
+            lint: Lint::default(),
+            fmt: Fmt::default(),
+            dependencies: HashMap::new(),
+            lua_dependencies: HashMap::new(),
         }
     }
 
@@ -154,6 +186,11 @@ impl ProjectConfig {
             paths: Paths::default(),
             run: Run::default(),
             build: Build::default(),
+            targets: Targets::default(),
+            lint: Lint::default(),
+            fmt: Fmt::default(),
+            dependencies: HashMap::new(),
+            lua_dependencies: HashMap::new(),
         }
     }
 
@@ -202,6 +239,20 @@ impl ProjectConfig {
             pkg.description.to_owned(),
         );
 
+        // Read by the debugger bootstrap (lua/debug.lua)
+        if let Context::Run = context {
+            if console::get_command_line_settings().has_flag("debug") {
+                let conf = config::get();
+
+                map.insert("LOVER_DEBUG".to_string(), "1".to_string());
+                map.insert("LOVER_DEBUG_HOST".to_string(), conf.run.debug_host);
+                map.insert(
+                    "LOVER_DEBUG_PORT".to_string(),
+                    conf.run.debug_port.to_string(),
+                );
+            }
+        }
+
         return map;
     }
 }
@@ -225,7 +276,11 @@ pub struct Package {
 
     #[serde(default = "Package::default_icon")]
     #[serde(skip_serializing_if = "Package::is_default_icon")]
-    pub icon: String,
+    pub icon: Icon,
+
+    // LOVE release used for building, latest if not specified
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub love_version: Option<String>,
 }
 
 impl Package {
@@ -238,6 +293,7 @@ impl Package {
             author: String::new(),
             version: Self::default_version(),
             icon: Self::default_icon(),
+            love_version: None,
         }
     }
 
@@ -279,15 +335,106 @@ impl Package {
         "1.0".to_string()
     }
 
-    pub fn default_icon() -> String {
-        "icon.png".to_string()
+    pub fn default_icon() -> Icon {
+        Icon::Path("icon.png".to_string())
     }
 
-    pub fn is_default_icon(icon: &String) -> bool {
+    pub fn is_default_icon(icon: &Icon) -> bool {
         return &Self::default_icon() == icon;
     }
 }
 
+// `icon = "icon.png"` or a `[package.icon]` table
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum Icon {
+    Path(String),
+    Table(IconTable),
+}
+
+impl Icon {
+    pub fn get_filter(&self) -> IconFilter {
+        match self {
+            Self::Path(_) => IconFilter::default(),
+            Self::Table(table) => table.filter.to_owned(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Path(path) => path.is_empty(),
+            Self::Table(table) => table.path.is_empty() && table.sizes.is_empty(),
+        }
+    }
+
+    // Path of the image that should be scaled to `size`.
+    // Prefers an image made for that exact size, then the main one, then the biggest one.
+    pub fn get_source(&self, size: u32) -> Option<String> {
+        match self {
+            Self::Path(path) => {
+                if path.is_empty() {
+                    None
+                } else {
+                    Some(path.to_owned())
+                }
+            }
+            Self::Table(table) => {
+                if let Some(path) = table.sizes.get(&size.to_string()) {
+                    return Some(path.to_owned());
+                }
+
+                if !table.path.is_empty() {
+                    return Some(table.path.to_owned());
+                }
+
+                table
+                    .sizes
+                    .iter()
+                    .filter_map(|(k, v)| k.parse::<u32>().ok().map(|k| (k, v)))
+                    .max_by_key(|(k, _)| *k)
+                    .map(|(_, v)| v.to_owned())
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+pub struct IconTable {
+    #[serde(default = "IconTable::default_path")]
+    pub path: String,
+
+    #[serde(default = "IconFilter::default")]
+    pub filter: IconFilter,
+
+    // Separate images for specific sizes, e.g. `16 = "icon_16.png"`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub sizes: HashMap<String, String>,
+}
+
+impl IconTable {
+    // Same as `icon = "icon.png"`
+    fn default_path() -> String {
+        "icon.png".to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum IconFilter {
+    Nearest,
+    Linear,
+    Cubic,
+    Gaussian,
+    Lanczos,
+}
+
+impl IconFilter {
+    pub fn default() -> Self {
+        Self::Lanczos
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct Paths {
     #[serde(default = "Paths::default_main")]
@@ -299,6 +446,10 @@ pub struct Paths {
     #[serde(default = "Paths::default_build")]
     pub build: String,
 
+    // Directory inside the game archive the Lua dependencies are vendored into
+    #[serde(default = "Paths::default_lib")]
+    pub lib: String,
+
     source: Option<String>, // old 'main
 }
 
@@ -308,6 +459,7 @@ impl Paths {
             main: Self::default_main(),
             exclude: Self::default_exclude(),
             build: Self::default_build(),
+            lib: Self::default_lib(),
             source: None,
         }
     }
@@ -324,6 +476,14 @@ impl Paths {
         "build".to_string()
     }
 
+    fn default_lib() -> String {
+        "lib".to_string()
+    }
+
+    pub fn get_lib_dir(&self) -> &str {
+        self.lib.trim_matches('/')
+    }
+
     pub fn get_root_dir(&self) -> PathBuf {
         find_project_dir().unwrap_or_else(|| {
             exit_err("Failed to find project directory");
@@ -335,7 +495,12 @@ impl Paths {
     }
 
     pub fn get_temp_dir(&self) -> PathBuf {
-        self.get_build_dir().join("temp")
+        self.get_temp_dir_at(&self.get_root_dir())
+    }
+
+    // Temp directory of a project located at `root`, which doesn't have to be the current one
+    pub fn get_temp_dir_at(&self, root: &Path) -> PathBuf {
+        root.join(&self.build).join("temp")
     }
 
     pub fn get_main_dir(&self) -> PathBuf {
@@ -459,6 +624,13 @@ impl Paths {
         self.filter_files(false)
     }
 
+    pub fn get_lua_files(&self) -> Vec<PathBuf> {
+        self.get_files()
+            .into_iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "lua"))
+            .collect()
+    }
+
     pub fn is_default(&self) -> bool {
         return self == &Self::default();
     }
@@ -468,15 +640,28 @@ impl Paths {
 pub struct Run {
     #[serde(default = "Run::default_default_run_args")]
     pub default_args: Vec<String>,
+
+    // Extra arguments of `lover run --instances`, keyed by the instance number
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub instances: HashMap<String, Vec<String>>,
 }
 
 impl Run {
     pub fn default() -> Self {
         Self {
             default_args: Self::default_default_run_args(),
+            instances: HashMap::new(),
         }
     }
 
+    pub fn get_instance_args(&self, instance: usize) -> Vec<String> {
+        self.instances
+            .get(&instance.to_string())
+            .cloned()
+            .unwrap_or_default()
+    }
+
     fn default_default_run_args() -> Vec<String> {
         Vec::new()
     }
@@ -546,6 +731,251 @@ impl Env {
     }
 }
 
+#[derive(Serialize, Deserialize, PartialEq)]
+pub struct Lint {
+    // Severity of each rule: "error", "warning" or "off"
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub rules: HashMap<String, Severity>,
+
+    // Globals defined outside of the project's scripts, e.g. by C libraries
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub globals: Vec<String>,
+}
+
+impl Lint {
+    pub fn default() -> Self {
+        Self {
+            rules: HashMap::new(),
+            globals: Vec::new(),
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub struct Fmt {
+    #[serde(default = "Fmt::default_indent_style")]
+    pub indent_style: IndentStyle,
+
+    // Spaces per level when `indent_style` is "spaces"
+    #[serde(default = "Fmt::default_indent_width")]
+    pub indent_width: usize,
+
+    // "double", "single" or "keep"
+    #[serde(default = "Fmt::default_quote_style")]
+    pub quote_style: QuoteStyle,
+}
+
+impl Fmt {
+    pub fn default() -> Self {
+        Self {
+            indent_style: Self::default_indent_style(),
+            indent_width: Self::default_indent_width(),
+            quote_style: Self::default_quote_style(),
+        }
+    }
+
+    fn default_indent_style() -> IndentStyle {
+        IndentStyle::Spaces
+    }
+
+    fn default_indent_width() -> usize {
+        4
+    }
+
+    fn default_quote_style() -> QuoteStyle {
+        QuoteStyle::Double
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub struct Targets {
+    #[serde(default = "Windows::default")]
+    #[serde(skip_serializing_if = "Windows::is_default")]
+    pub win64: Windows,
+
+    #[serde(default = "Windows::default")]
+    #[serde(skip_serializing_if = "Windows::is_default")]
+    pub win32: Windows,
+}
+
+impl Targets {
+    pub fn default() -> Self {
+        Self {
+            win64: Windows::default(),
+            win32: Windows::default(),
+        }
+    }
+
+    pub fn get_windows(&self, arch: &Arch) -> &Windows {
+        match arch {
+            Arch::X86_64 => &self.win64,
+            Arch::X86_32 => &self.win32,
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub struct Windows {
+    // Embed the game into `lovec.exe` which opens a console window
+    #[serde(default)]
+    pub console: bool,
+
+    // Remove files the game doesn't need, like `lovec.exe` or `readme.txt`
+    #[serde(default)]
+    pub strip: bool,
+
+    pub dpi_awareness: Option<DpiAwareness>,
+    pub execution_level: Option<ExecutionLevel>,
+
+    #[serde(default)]
+    pub long_path_aware: bool,
+}
+
+impl Windows {
+    pub fn default() -> Self {
+        Self {
+            console: false,
+            strip: false,
+            dpi_awareness: None,
+            execution_level: None,
+            long_path_aware: false,
+        }
+    }
+
+    pub fn get_exe_name(&self) -> &str {
+        if self.console {
+            "lovec.exe"
+        } else {
+            "love.exe"
+        }
+    }
+
+    // Files from the LOVE zip that are removed with `strip = true`
+    pub fn get_stripped_files(&self) -> Vec<&str> {
+        let other_exe = if self.console {
+            "love.exe"
+        } else {
+            "lovec.exe"
+        };
+
+        vec![
+            other_exe,
+            "changes.txt",
+            "readme.txt",
+            "love.ico",
+            "game.ico",
+        ]
+    }
+
+    pub fn has_manifest(&self) -> bool {
+        self.dpi_awareness.is_some() || self.execution_level.is_some() || self.long_path_aware
+    }
+
+    pub fn get_manifest(&self) -> String {
+        let level = self
+            .execution_level
+            .as_ref()
+            .unwrap_or(&ExecutionLevel::AsInvoker)
+            .to_str();
+
+        let mut settings: Vec<String> = Vec::new();
+
+        if let Some(dpi) = &self.dpi_awareness {
+            settings.push(format!(
+                "<dpiAware xmlns=\"http://schemas.microsoft.com/SMI/2005/WindowsSettings\">{}</dpiAware>",
+                dpi.get_dpi_aware()
+            ));
+
+            if let Some(awareness) = dpi.get_dpi_awareness() {
+                settings.push(format!(
+                    "<dpiAwareness xmlns=\"http://schemas.microsoft.com/SMI/2016/WindowsSettings\">{}</dpiAwareness>",
+                    awareness
+                ));
+            }
+        }
+
+        if self.long_path_aware {
+            settings.push(
+                "<longPathAware xmlns=\"http://schemas.microsoft.com/SMI/2016/WindowsSettings\">true</longPathAware>"
+                    .to_string(),
+            );
+        }
+
+        let settings_str: String = settings.iter().map(|s| format!("      {}\n", s)).collect();
+
+        format!(
+            include_str!("windows/manifest.xml"),
+            level = level,
+            settings = settings_str
+        )
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DpiAwareness {
+    Unaware,
+    System,
+    PerMonitor,
+    PerMonitorV2,
+}
+
+impl DpiAwareness {
+    // Value for the legacy `dpiAware` element (Windows 8.1 and older)
+    pub fn get_dpi_aware(&self) -> &str {
+        match self {
+            Self::Unaware => "false",
+            Self::System => "true",
+            Self::PerMonitor | Self::PerMonitorV2 => "true/pm",
+        }
+    }
+
+    // Value for the `dpiAwareness` element (Windows 10 1607 and newer)
+    pub fn get_dpi_awareness(&self) -> Option<&str> {
+        match self {
+            Self::Unaware | Self::System => None,
+            Self::PerMonitor => Some("PerMonitor"),
+            Self::PerMonitorV2 => Some("PerMonitorV2, PerMonitor"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExecutionLevel {
+    AsInvoker,
+    HighestAvailable,
+    RequireAdministrator,
+}
+
+impl ExecutionLevel {
+    pub fn to_str(&self) -> &str {
+        match self {
+            Self::AsInvoker => "asInvoker",
+            Self::HighestAvailable => "highestAvailable",
+            Self::RequireAdministrator => "requireAdministrator",
+        }
+    }
+}
+
 pub fn find_project_config() -> Option<PathBuf> {
     let mut current = env::current_dir().unwrap();
 
@@ -588,6 +1018,80 @@ pub fn find_project_dir() -> Option<PathBuf> {
     }
 }
 
+static LOVE_VERSION: OnceLock<Option<String>> = OnceLock::new();
+
+// `love_version` of the current project, if there's any. Loaded once and an invalid
+// config is ignored here, since commands that need the project report it themselves.
+pub fn get_love_version() -> Option<String> {
+    LOVE_VERSION
+        .get_or_init(|| {
+            let string = std::fs::read_to_string(find_project_config()?).ok()?;
+
+            ProjectConfig::parse_str(&string).ok()?.package.love_version
+        })
+        .clone()
+}
+
+// Projects built or run on this machine, used to find out which dependency files are still needed
+#[derive(Serialize, Deserialize)]
+pub struct KnownProjects {
+    #[serde(default)]
+    pub paths: Vec<PathBuf>,
+}
+
+impl KnownProjects {
+    pub fn get_path() -> PathBuf {
+        config::get_dir().join("projects.toml")
+    }
+
+    pub fn get() -> Self {
+        let empty = Self { paths: Vec::new() };
+
+        std::fs::read_to_string(Self::get_path())
+            .ok()
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or(empty)
+    }
+
+    pub fn save(&self) {
+        let path = Self::get_path();
+
+        let res = std::fs::create_dir_all(path.parent().unwrap())
+            .map_err(|err| err.to_string())
+            .and_then(|_| toml::to_string_pretty(&self).map_err(|err| err.to_string()))
+            .and_then(|text| std::fs::write(&path, text).map_err(|err| err.to_string()));
+
+        if let Err(err) = res {
+            print_warn(format!("Failed to save known projects: {}", err));
+        }
+    }
+
+    // Projects that still exist
+    pub fn get_configs(&self) -> Vec<(PathBuf, ProjectConfig)> {
+        self.paths
+            .iter()
+            .filter_map(|dir| {
+                let text = std::fs::read_to_string(dir.join(PROJECT_FILE)).ok()?;
+                Some((dir.to_owned(), ProjectConfig::parse_str(&text).ok()?))
+            })
+            .collect()
+    }
+}
+
+pub fn register_project() {
+    let dir = match find_project_dir().and_then(|dir| dir.canonicalize().ok()) {
+        Some(dir) => dir,
+        None => return,
+    };
+
+    let mut known = KnownProjects::get();
+
+    if !known.paths.contains(&dir) {
+        known.paths.push(dir);
+        known.save();
+    }
+}
+
 pub fn get() -> ProjectConfig {
     let path = find_project_config().unwrap_or_else(|| {
         exit_err(format!(
@@ -622,6 +1126,41 @@ mod tests {
         assert_eq!(project.paths.main, "src");
     }
 
+    #[test]
+    fn parse_icon_table() {
+        let project =
+            ProjectConfig::parse_str(include_str!("testData/projects/icon.toml")).unwrap();
+        let icon = project.package.icon;
+
+        assert!(icon.get_filter() == IconFilter::Nearest);
+        assert_eq!(icon.get_source(16).unwrap(), "icon_16.png");
+        assert_eq!(icon.get_source(32).unwrap(), "icon.png");
+
+        let project =
+            ProjectConfig::parse_str("[package]\nname = \"a\"\nicon = { filter = \"nearest\" }")
+                .unwrap();
+
+        assert!(!project.package.icon.is_empty());
+        assert_eq!(project.package.icon.get_source(32).unwrap(), "icon.png");
+    }
+
+    #[test]
+    fn windows_manifest() {
+        let project =
+            ProjectConfig::parse_str(include_str!("testData/projects/windows.toml")).unwrap();
+        let win = project.targets.get_windows(&Arch::X86_64);
+
+        assert!(win.console);
+        assert!(win.has_manifest());
+        assert!(!project.targets.win32.has_manifest());
+        assert!(win.get_stripped_files().contains(&"love.exe"));
+
+        let manifest = win.get_manifest();
+
+        assert!(manifest.contains("PerMonitorV2"));
+        assert!(manifest.contains("level=\"asInvoker\""));
+    }
+
     #[test]
     fn main_script_finding() {
         let path = Paths::find_main_script_at("src/testData/projects/project")
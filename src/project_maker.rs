@@ -80,6 +80,7 @@ pub fn create(name: String, path: &Path) {
         description: "".to_string(),
         version: Package::default_version(),
         icon: Package::default_icon(),
+        love_version: None,
     };
 
     let project = ProjectConfig::from_package(pkg);
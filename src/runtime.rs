@@ -0,0 +1,140 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    actions::CommandRunner,
+    console::{exit_err, print_note, print_step, print_warn},
+    deps::{self, Dependency},
+    files,
+};
+
+#[cfg(target_os = "linux")]
+use crate::appimage;
+
+#[cfg(target_os = "windows")]
+use crate::actions::Extractor;
+
+// Dependency that provides LOVE binaries for the current platform
+pub fn get_dep_name() -> Option<&'static str> {
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    return Some("love-linux");
+
+    #[cfg(all(target_os = "windows", target_arch = "x86_64"))]
+    return Some("love-win64");
+
+    #[cfg(all(target_os = "windows", target_arch = "x86"))]
+    return Some("love-win32");
+
+    #[allow(unreachable_code)]
+    None
+}
+
+// Extracted LOVE binaries, e.g. `deps/love/love-linux@11.4`
+pub fn get_dir(dep: &Dependency) -> PathBuf {
+    deps::get_dir().join("love").join(dep.get_display_name())
+}
+
+fn get_binary(dir: &Path) -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        // Prints to the console, unlike love.exe
+        let lovec = dir.join("lovec.exe");
+
+        if lovec.exists() {
+            return lovec;
+        }
+
+        return dir.join("love.exe");
+    }
+
+    #[allow(unreachable_code)]
+    dir.join("bin").join("love")
+}
+
+// Extracts into a temporary directory first, so an interrupted extraction isn't mistaken for a complete one
+fn extract(dep: &Dependency, dir: &Path) {
+    let mut tmp = dir.to_owned().into_os_string();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let _ = fs::remove_dir_all(&tmp);
+    let _ = fs::remove_dir_all(dir);
+
+    print_step(format!("Extracting {}", dep.get_display_name()));
+
+    #[cfg(target_os = "linux")]
+    {
+        let squashfs = tmp.with_extension("squashfs");
+
+        appimage::extract_squashfs(&dep.get_path(), &squashfs);
+        appimage::extract_squashfs_dir(&squashfs, &tmp);
+
+        let _ = fs::remove_file(&squashfs);
+    }
+
+    #[cfg(target_os = "windows")]
+    Extractor::new(dep.get_path())
+        .add_progress_bar("Extracting LOVE")
+        .extract(&tmp);
+
+    fs::rename(&tmp, dir).unwrap_or_else(|err| {
+        exit_err(format!(
+            "Failed to move '{}': {}",
+            tmp.to_str().unwrap(),
+            err
+        ));
+    });
+}
+
+// Command running a specific LOVE version from the dependency directory.
+// The release is installed and extracted if needed.
+pub fn get_love_command(version: &str) -> CommandRunner {
+    let name = get_dep_name().unwrap_or_else(|| {
+        exit_err("Running a specific LOVE version is not supported on this platform.");
+    });
+
+    let mut dep = deps::get_dep_or_crash(name);
+    dep.set_version(version);
+
+    if !dep.is_installed() {
+        print_warn(format!("LOVE {} is not installed.", version));
+        deps::install_deps(vec![deps::get_dep_or_crash(dep.get_display_name())], None);
+    }
+
+    let dir = get_dir(&dep);
+    let binary = get_binary(&dir);
+
+    if !binary.exists() {
+        files::create_dir(dir.parent().unwrap());
+        extract(&dep, &dir);
+    }
+
+    if !binary.exists() {
+        exit_err(format!(
+            "'{}' could not be found.",
+            binary.to_str().unwrap()
+        ));
+    }
+
+    print_note(format!("Using LOVE {}", version));
+
+    #[allow(unused_mut)]
+    let mut cmd = CommandRunner::new(binary.to_str().unwrap());
+
+    // The AppImage normally sets this in AppRun
+    #[cfg(target_os = "linux")]
+    {
+        let mut lib_path = dir.join("lib").into_os_string();
+
+        if let Some(current) = std::env::var_os("LD_LIBRARY_PATH") {
+            lib_path.push(":");
+            lib_path.push(current);
+        }
+
+        cmd.set_env("LD_LIBRARY_PATH", lib_path.to_str().unwrap());
+    }
+
+    cmd
+}
@@ -0,0 +1,296 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    config,
+    console::{
+        self, confirm_or_exit, exit_err, print_note, print_significant, print_step, print_success,
+        print_warn, ProgressBar,
+    },
+    deps::{self, GitHubRelease},
+    http::{self, Downloadable},
+};
+
+const REPO_OWNER: &str = "Wolfyxon";
+const REPO: &str = "lover";
+const CHECK_INTERVAL: u64 = 60 * 60 * 24;
+
+#[derive(Serialize, Deserialize)]
+struct UpdateCheck {
+    checked_at: u64,
+    latest: String,
+}
+
+pub fn get_current_version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+// Compares dot separated versions. A leading `v` is ignored and missing parts count as 0.
+pub fn is_newer(version: &str, than: &str) -> bool {
+    let parse = |ver: &str| -> Vec<u64> {
+        ver.trim_start_matches('v')
+            .split('.')
+            .map(|part| part.parse::<u64>().unwrap_or(0))
+            .collect()
+    };
+
+    let mut a = parse(version);
+    let mut b = parse(than);
+    let len = a.len().max(b.len());
+
+    a.resize(len, 0);
+    b.resize(len, 0);
+
+    a > b
+}
+
+// Pattern of the release asset for the current OS and architecture
+pub fn get_asset_pattern() -> Option<&'static str> {
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    return Some("^lover$");
+
+    #[cfg(all(target_os = "windows", target_arch = "x86_64"))]
+    return Some(r"^lover\.exe$");
+
+    #[allow(unreachable_code)]
+    None
+}
+
+pub fn fetch_latest_release() -> Result<GitHubRelease, String> {
+    let url = deps::get_gh_release_url(&deps::get_api_url(), REPO_OWNER, REPO, "latest");
+
+    http::try_fetch_struct(&url)
+}
+
+fn get_check_path() -> PathBuf {
+    config::get_dir().join("update_check.toml")
+}
+
+fn get_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+fn save_check(latest: &str) {
+    let check = UpdateCheck {
+        checked_at: get_timestamp(),
+        latest: latest.to_string(),
+    };
+
+    let path = get_check_path();
+    let _ = fs::create_dir_all(path.parent().unwrap());
+
+    if let Ok(text) = toml::to_string(&check) {
+        let _ = fs::write(path, text);
+    }
+}
+
+// Latest Lover version, fetched at most once a day. Failures are silent.
+pub fn get_latest_version() -> Option<String> {
+    let cached: Option<UpdateCheck> = fs::read_to_string(get_check_path())
+        .ok()
+        .and_then(|text| toml::from_str(&text).ok());
+
+    if let Some(check) = &cached {
+        if get_timestamp().saturating_sub(check.checked_at) < CHECK_INTERVAL {
+            return Some(check.latest.to_owned());
+        }
+    }
+
+    if !deps::can_download() {
+        return cached.map(|check| check.latest);
+    }
+
+    // Saved even on failure, so an unreachable API isn't requested every time
+    let latest = match fetch_latest_release() {
+        Ok(release) => release.tag_name,
+        Err(_) => cached
+            .map(|check| check.latest)
+            .unwrap_or(get_current_version().to_string()),
+    };
+
+    save_check(&latest);
+    Some(latest)
+}
+
+pub fn notify_if_outdated() {
+    if let Some(latest) = get_latest_version() {
+        if is_newer(&latest, get_current_version()) {
+            println!();
+            print_note(format!(
+                "Lover {} is available. Use `lover self-update` to update.",
+                latest
+            ));
+        }
+    }
+}
+
+pub fn self_update() {
+    let pattern = get_asset_pattern().unwrap_or_else(|| {
+        exit_err(format!(
+            "Self update is not supported on this platform. Download Lover from https://github.com/{}/{}/releases",
+            REPO_OWNER, REPO
+        ));
+    });
+
+    if !deps::can_download() {
+        exit_err("Lover can't be updated in offline mode.");
+    }
+
+    print_step("Checking for updates...");
+
+    let release = fetch_latest_release().unwrap_or_else(|err| exit_err(err));
+    let current = get_current_version();
+
+    save_check(&release.tag_name);
+
+    if !is_newer(&release.tag_name, current) {
+        print_success(format!("Lover is up to date ({}).", current));
+        return;
+    }
+
+    let asset = release.get_asset_matching(pattern).unwrap_or_else(|| {
+        exit_err(format!(
+            "Release {} has no asset for this platform.",
+            release.tag_name
+        ));
+    });
+
+    let exe = env::current_exe()
+        .and_then(|path| path.canonicalize())
+        .unwrap_or_else(|err| exit_err(format!("Failed to find Lover's executable: {}", err)));
+
+    print_significant(
+        "Updating Lover",
+        format!("{} -> {}", current, release.tag_name),
+    );
+    println!("Executable: {}", exe.to_str().unwrap());
+    println!("Release notes: {}\n", release.html_url);
+
+    confirm_or_exit("Proceed with the update?");
+
+    let sha256 = asset.get_sha256();
+
+    if sha256.is_none() {
+        print_warn("The release has no checksum. It will only be verified by running it.");
+    }
+
+    let mut new_exe = exe.to_owned().into_os_string();
+    new_exe.push(".new");
+    let new_exe = PathBuf::from(new_exe);
+
+    let mut download = Downloadable::request(deps::resolve_mirror_url(&asset.browser_download_url));
+    download.set_sha256(sha256);
+
+    let mut bar = ProgressBar::new(download.len().unwrap_or(0) as usize);
+    bar.set_prefix(format!("{} Downloading", console::get_step_prefix()))
+        .memory_mode();
+
+    download
+        .download(&new_exe, |progress| bar.update(progress.min(bar.max)))
+        .unwrap_or_else(|err| exit_err(format!("Failed to download the update: {}", err)));
+
+    bar.finish();
+
+    let res = verify(&new_exe, &release.tag_name).and_then(|_| replace(&exe, &new_exe));
+
+    if let Err(err) = res {
+        let _ = fs::remove_file(&new_exe);
+        exit_err(format!("Failed to update Lover: {}", err));
+    }
+
+    print_success(format!("Lover updated to {}", release.tag_name));
+}
+
+// Makes sure the downloaded binary runs and reports the expected version
+fn verify(exe: &Path, tag: &str) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(exe, fs::Permissions::from_mode(0o755))
+            .map_err(|err| format!("Failed to assign exec permissions: {}", err))?;
+    }
+
+    let output = Command::new(exe)
+        .arg("--version")
+        .output()
+        .map_err(|err| format!("The new version can't be run: {}", err))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = tag.trim_start_matches('v');
+
+    if !output.status.success() || !stdout.contains(version) {
+        return Err(format!(
+            "The new version reported '{}' instead of {}",
+            stdout.trim(),
+            version
+        ));
+    }
+
+    Ok(())
+}
+
+// Renames are atomic as long as both files are in the same directory
+fn replace(exe: &Path, new_exe: &Path) -> Result<(), String> {
+    // A running executable can't be overwritten on Windows, but it can be renamed
+    #[cfg(windows)]
+    {
+        let old = get_old_path(exe);
+        let _ = fs::remove_file(&old);
+
+        fs::rename(exe, &old).map_err(|err| format!("Failed to move the old version: {}", err))?;
+    }
+
+    fs::rename(new_exe, exe).map_err(|err| format!("Failed to replace the executable: {}", err))
+}
+
+#[cfg(windows)]
+fn get_old_path(exe: &Path) -> PathBuf {
+    let mut old = exe.to_owned().into_os_string();
+    old.push(".old");
+    PathBuf::from(old)
+}
+
+// Removes the executable left over by an update on Windows
+pub fn remove_old_exe() {
+    #[cfg(windows)]
+    if let Ok(exe) = env::current_exe() {
+        let _ = fs::remove_file(get_old_path(&exe));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_versions() {
+        assert!(is_newer("v2.1.1", "2.1.0"));
+        assert!(is_newer("2.10.0", "2.9.3"));
+        assert!(is_newer("v3", "2.9.9"));
+        assert!(!is_newer("v2.1.1", "2.1.1"));
+        assert!(!is_newer("2.0.9", "2.1"));
+        assert!(!is_newer("3.0", "3"));
+        assert!(!is_newer("v1.2.0", "1.2"));
+        assert!(is_newer("1.2.1", "1.2"));
+    }
+
+    #[test]
+    fn pick_asset() {
+        let release: GitHubRelease =
+            serde_json::from_str(include_str!("testData/releases/lover.json")).unwrap();
+
+        if let Some(pattern) = get_asset_pattern() {
+            let asset = release.get_asset_matching(pattern).unwrap();
+            assert!(!asset.name.ends_with(".sig"));
+        }
+    }
+}
@@ -1,9 +1,11 @@
+use std::collections::HashMap;
 use std::fs;
 use std::io::{BufReader, Read, Write};
 use std::path::{Path, PathBuf};
 
-use image::imageops::FilterType;
-use image::{GenericImageView, ImageFormat, ImageReader};
+use image::codecs::ico::{IcoEncoder, IcoFrame};
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ExtendedColorType, ImageReader, RgbaImage};
 
 use crate::actions::{Archiver, CommandRunner, Extractor};
 use crate::config::Config;
@@ -13,7 +15,9 @@ use crate::console::{
 };
 use crate::deps;
 use crate::deps::Dependency;
-use crate::project_config::{self, Package};
+use crate::lock::{self, Lockfile};
+use crate::lua_deps;
+use crate::project_config::{self, Icon, IconFilter, Package, ProjectConfig};
 use crate::{actions, appimage, config, console, files};
 
 pub enum Arch {
@@ -164,10 +168,8 @@ pub fn get_targets<'a>() -> Vec<BuildTarget<'a>> {
     ]
 }
 
-pub fn gen_module() -> String {
-    let project = project_config::get();
-
-    let map = project.get_env_map(actions::Context::Build);
+// Code implementing the Lover constants
+pub fn gen_env_code(map: HashMap<String, String>) -> String {
     let mut res = include_str!("lua/env.lua").to_string();
 
     res += "loverConsts = {\n";
@@ -176,10 +178,35 @@ pub fn gen_module() -> String {
         res += format!("    {} = '{}',\n", key, val).as_str();
     }
 
-    res += "}";
+    res += "}\n";
+    res
+}
 
+pub fn wrap_generated_code(code: String) -> String {
     let header = "---- Auto generated by Lover ----";
-    format!("{}\n{}\n{}\n\n", header, res, "-".repeat(header.len()))
+    format!("{}\n{}\n{}\n\n", header, code, "-".repeat(header.len()))
+}
+
+// Clients used by `lover run`. They are only built into the game in the temp directory.
+#[derive(Default)]
+pub struct ModuleOptions {
+    pub hot: bool,   // hot reload client (lua/hot.lua)
+    pub debug: bool, // debugger bootstrap (lua/debug.lua)
+}
+
+pub fn gen_module(project: &ProjectConfig, options: &ModuleOptions) -> String {
+    let mut res = gen_env_code(project.get_env_map(actions::Context::Build));
+    res += &lua_deps::gen_require_code(project);
+
+    if options.hot {
+        res += include_str!("lua/hot.lua");
+    }
+
+    if options.debug {
+        res += include_str!("lua/debug.lua");
+    }
+
+    wrap_generated_code(res)
 }
 
 pub fn get_target<'a>(name: impl Into<String>) -> Option<BuildTarget<'a>> {
@@ -212,6 +239,65 @@ pub fn get_platform_target_name() -> String {
     return "love".to_string();
 }
 
+// File launched by `lover run --target`. None if the target can't be run.
+pub fn get_artifact(name: &str, project: &ProjectConfig) -> Option<PathBuf> {
+    let build_dir = project.paths.get_build_dir();
+    let pkg_name = &project.package.name;
+
+    match name {
+        "love" => Some(build_dir.join(format!("{}.love", pkg_name))),
+        "linux" => Some(build_dir.join(format!("{}.AppImage", pkg_name))),
+        "win64" | "win32" => Some(build_dir.join(name).join(format!("{}.exe", pkg_name))),
+        _ => None,
+    }
+}
+
+// Game built with the `lover run` clients, separate from the `love` target
+pub fn get_run_love_path(project: &ProjectConfig) -> PathBuf {
+    project
+        .paths
+        .get_temp_dir()
+        .join(format!("{}.love", project.package.name))
+}
+
+pub fn build_run_love(project: &ProjectConfig, options: &ModuleOptions) -> PathBuf {
+    let output = get_run_love_path(project);
+
+    files::create_dir(project.paths.get_temp_dir());
+    archive_game(project, &output, options);
+
+    output
+}
+
+// Whether the artifact is missing or a project file, the lockfile or a local Lua dependency
+// was modified after it was built
+pub fn is_outdated(artifact: &Path, project: &ProjectConfig) -> bool {
+    let built_at = match fs::metadata(artifact).and_then(|meta| meta.modified()) {
+        Ok(time) => time,
+        Err(_) => return true,
+    };
+
+    let root = project.paths.get_root_dir();
+    let mut inputs = project.paths.get_files();
+
+    inputs.push(root.join(project_config::PROJECT_FILE));
+    inputs.push(root.join(lock::LOCK_FILE));
+
+    for dep in project.lua_dependencies.values() {
+        let dir = dep.path.as_ref().map(|path| root.join(path));
+
+        if let Some(dir) = dir.filter(|dir| dir.is_dir()) {
+            inputs.append(&mut files::get_file_tree(dir));
+        }
+    }
+
+    inputs.iter().any(|path| {
+        fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|time| time > built_at)
+    })
+}
+
 pub fn get_target_or_crash<'a>(name: impl Into<String>) -> BuildTarget<'a> {
     let name_str = name.into();
 
@@ -247,63 +333,113 @@ pub fn get_targets_by_strings<'a>(names: Vec<String>) -> Vec<BuildTarget<'a>> {
     res
 }
 
-pub fn rcedit_add_icon(args: &mut Vec<String>, package: &Package, path: &PathBuf) {
-    let icon_str_path = &package.icon;
+pub const ICO_SIZES: [u32; 7] = [16, 24, 32, 48, 64, 128, 256];
 
-    if icon_str_path.is_empty() {
-        return;
+pub fn get_filter_type(filter: &IconFilter) -> FilterType {
+    match filter {
+        IconFilter::Nearest => FilterType::Nearest,
+        IconFilter::Linear => FilterType::Triangle,
+        IconFilter::Cubic => FilterType::CatmullRom,
+        IconFilter::Gaussian => FilterType::Gaussian,
+        IconFilter::Lanczos => FilterType::Lanczos3,
     }
+}
 
-    let icon_path = Path::new(&icon_str_path);
-    let icon_out_path = path.join("game.ico");
+pub fn load_image(path_str: &str) -> Result<DynamicImage, String> {
+    let path = Path::new(path_str);
 
-    if !icon_path.exists() {
-        print_warn(format!("Icon at path '{}' not found.", icon_str_path));
-        return;
+    if !path.exists() {
+        return Err(format!("Icon at path '{}' not found.", path_str));
     }
 
-    if icon_path.is_dir() {
-        print_warn(format!("Icon '{}' is a directory!", icon_str_path));
-        return;
+    if path.is_dir() {
+        return Err(format!("Icon '{}' is a directory!", path_str));
     }
 
-    let file = files::open(icon_path);
-    let reader = BufReader::new(file);
+    let reader = BufReader::new(files::open(path));
 
-    match ImageReader::new(reader).with_guessed_format() {
-        Ok(img_reader) => match img_reader.decode() {
-            Ok(mut img) => {
-                print_step("Converting icon to the ICO format");
+    ImageReader::new(reader)
+        .with_guessed_format()
+        .map_err(|err| format!("Failed to read image '{}': {}", path_str, err))?
+        .decode()
+        .map_err(|err| format!("Failed to decode image: '{}': {}", path_str, err))
+}
 
-                let (mut w, mut h) = img.dimensions();
+// Scales the image to fit a `size`x`size` square, keeping the aspect ratio.
+pub fn gen_ico_frame(img: &DynamicImage, size: u32, filter: FilterType) -> RgbaImage {
+    let scaled = img.resize(size, size, filter).to_rgba8();
+    let mut frame = RgbaImage::new(size, size);
 
-                w = w.clamp(16, 256);
-                h = h.clamp(16, 256);
+    let x = (size - scaled.width()) / 2;
+    let y = (size - scaled.height()) / 2;
 
-                img = img.resize(w, h, FilterType::Nearest);
+    imageops::overlay(&mut frame, &scaled, x as i64, y as i64);
 
-                let save_res = img.save_with_format(&icon_out_path, ImageFormat::Ico);
+    frame
+}
 
-                if save_res.is_err() {
-                    print_warn(format!(
-                        "Failed to save new icon: {}",
-                        save_res.err().unwrap()
-                    ));
-                    return;
-                }
+pub fn gen_ico(icon: &Icon) -> Result<Vec<u8>, String> {
+    let filter = get_filter_type(&icon.get_filter());
+    let mut images: HashMap<String, DynamicImage> = HashMap::new();
+    let mut frames: Vec<IcoFrame> = Vec::new();
 
-                args.append(&mut vec![
-                    "--set-icon".to_string(),
-                    icon_out_path.to_str().unwrap().to_string(),
-                ]);
-            }
-            Err(err) => print_warn(format!(
-                "Failed to decode image: '{}': {}",
-                icon_str_path, err
-            )),
-        },
-        Err(err) => print_warn(format!("Failed to read image '{}': {}", icon_str_path, err)),
+    for size in ICO_SIZES {
+        let source = icon
+            .get_source(size)
+            .ok_or("No icon image specified".to_string())?;
+
+        if !images.contains_key(&source) {
+            images.insert(source.to_owned(), load_image(&source)?);
+        }
+
+        let frame = gen_ico_frame(&images[&source], size, filter);
+
+        frames.push(
+            IcoFrame::as_png(frame.as_raw(), size, size, ExtendedColorType::Rgba8)
+                .map_err(|err| format!("Failed to encode {}x{} icon: {}", size, size, err))?,
+        );
+    }
+
+    let mut buf: Vec<u8> = Vec::new();
+
+    IcoEncoder::new(&mut buf)
+        .encode_images(&frames)
+        .map_err(|err| format!("Failed to encode icon: {}", err))?;
+
+    Ok(buf)
+}
+
+pub fn rcedit_add_icon(args: &mut Vec<String>, package: &Package, path: &PathBuf) {
+    if package.icon.is_empty() {
+        return;
+    }
+
+    let icon_out_path = path.join("game.ico");
+
+    print_step("Converting icon to the ICO format");
+
+    let ico = match gen_ico(&package.icon) {
+        Ok(ico) => ico,
+        Err(err) => {
+            print_warn(err);
+            return;
+        }
     };
+
+    let save_res = fs::write(&icon_out_path, ico);
+
+    if save_res.is_err() {
+        print_warn(format!(
+            "Failed to save new icon: {}",
+            save_res.err().unwrap()
+        ));
+        return;
+    }
+
+    args.append(&mut vec![
+        "--set-icon".to_string(),
+        icon_out_path.to_str().unwrap().to_string(),
+    ]);
 }
 
 // for windows targets
@@ -313,10 +449,12 @@ pub fn build_windows_zip(arch: Arch) {
     let conf = config::get();
     let cmd_conf = console::get_command_line_settings();
     let project_conf = project_config::get();
-    let pkg = project_conf.package;
+    let win_conf = project_conf.targets.get_windows(&arch);
+    let pkg = &project_conf.package;
     let pkg_name = &pkg.name;
 
     let build_dir = &project_conf.paths.get_build_dir();
+    let temp = &project_conf.paths.get_temp_dir();
     let zip_path = &deps::get_dep_or_crash(format!("love-{}", &name)).get_path();
 
     let path = build_dir.join(&name);
@@ -327,7 +465,7 @@ pub fn build_windows_zip(arch: Arch) {
         .add_progress_bar("Extracting Windows Love2D files")
         .extract(&path);
 
-    let exe_src = path.join("love.exe");
+    let exe_src = path.join(win_conf.get_exe_name());
 
     if !exe_src.exists() {
         exit_err(format!(
@@ -336,8 +474,27 @@ pub fn build_windows_zip(arch: Arch) {
         ));
     }
 
+    let mut rcedit_args: Vec<String> = Vec::new();
+
+    if win_conf.has_manifest() {
+        let manifest_path = temp.join(format!("{}.manifest", &name));
+
+        print_step_verbose(&cmd_conf, "Generating application manifest");
+
+        fs::write(&manifest_path, win_conf.get_manifest()).unwrap_or_else(|err| {
+            exit_err(format!(
+                "Failed to write '{}': {}",
+                manifest_path.to_str().unwrap(),
+                err
+            ));
+        });
+
+        rcedit_args.push("--application-manifest".to_string());
+        rcedit_args.push(manifest_path.to_str().unwrap().to_string());
+    }
+
     print_step("Applying info with RCEdit");
-    apply_rcedit(&exe_src, &path, &conf, &pkg);
+    apply_rcedit(&exe_src, &path, &conf, pkg, rcedit_args);
 
     actions::append_file(
         love.as_path(),
@@ -357,6 +514,28 @@ pub fn build_windows_zip(arch: Arch) {
         ));
     });
 
+    if win_conf.strip {
+        print_step("Removing unneeded files");
+
+        for file_name in win_conf.get_stripped_files() {
+            let file_path = path.join(file_name);
+
+            if !file_path.exists() {
+                continue;
+            }
+
+            print_step_verbose(&cmd_conf, format!("Removing {}", file_name));
+
+            fs::remove_file(&file_path).unwrap_or_else(|err| {
+                print_warn(format!(
+                    "Failed to remove '{}': {}",
+                    file_path.to_str().unwrap(),
+                    err
+                ));
+            });
+        }
+    }
+
     if conf.build.zip {
         Archiver::new(&path)
             .add_progress_bar("Archiving build files")
@@ -364,10 +543,17 @@ pub fn build_windows_zip(arch: Arch) {
     }
 }
 
-fn apply_rcedit(exe: &PathBuf, project_root: &PathBuf, config: &Config, package: &Package) {
+fn apply_rcedit(
+    exe: &PathBuf,
+    project_root: &PathBuf,
+    config: &Config,
+    package: &Package,
+    mut extra_args: Vec<String>,
+) {
     let mut args = vec![exe.to_str().unwrap().to_string()];
 
     args.append(&mut package.get_rcedit_args());
+    args.append(&mut extra_args);
     rcedit_add_icon(&mut args, &package, &project_root);
 
     #[allow(unused_mut)]
@@ -391,21 +577,40 @@ fn build_virtual() {
 
 fn build_love() {
     let config = project_config::get();
+    let output = config
+        .paths
+        .get_build_dir()
+        .join(config.package.name.to_owned() + ".love");
+
+    archive_game(&config, &output, &ModuleOptions::default());
+}
+
+fn archive_game(config: &ProjectConfig, output: &Path, options: &ModuleOptions) {
     let src = config.paths.get_main_dir();
     let build = config.paths.get_build_dir();
     let temp = config.paths.get_temp_dir();
 
     let ignored_files = config.paths.get_ignored_files();
 
-    let output = build.join(config.package.name + ".love");
-
     actions::parse_all(&src);
+    lua_deps::check_collisions(config, &src);
 
     Archiver::new(&src)
         .add_progress_bar("Archiving game assets")
         .ignore_file(Path::new("conf.lua"))
         .ignore_files(&ignored_files)
-        .archive(&output);
+        .archive(output);
+
+    if !config.lua_dependencies.is_empty() {
+        let mut lockfile = lock::get().unwrap_or_else(Lockfile::new);
+
+        lua_deps::vendor(config, &mut lockfile, &[]);
+        actions::add_dir_to_archive(
+            output,
+            &lua_deps::get_vendor_dir(config),
+            Path::new(config.paths.get_lib_dir()),
+        );
+    }
 
     //Improve the search by not loading the entire tree
     //Also untested
@@ -417,7 +622,7 @@ fn build_love() {
     let out_conf_path = temp.join("conf.lua");
 
     let mut buf: Vec<u8> = Vec::new();
-    let mut module = gen_module().as_bytes().to_vec();
+    let mut module = gen_module(config, options).as_bytes().to_vec();
 
     buf.append(&mut module);
 
@@ -429,7 +634,7 @@ fn build_love() {
     let mut out_file = files::create(&out_conf_path);
     out_file.write_all(&mut buf).unwrap();
 
-    actions::add_to_archive(&output, &out_conf_path, Path::new("conf.lua"));
+    actions::add_to_archive(output, &out_conf_path, Path::new("conf.lua"));
 }
 
 fn build_linux() {
@@ -533,6 +738,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn ico_generation() {
+        let icon = Icon::Path("src/lua/template/icon.png".to_string());
+        let ico = gen_ico(&icon).unwrap();
+
+        // ICONDIR: reserved (2 bytes), type (2 bytes), image count (2 bytes)
+        let count = u16::from_le_bytes([ico[4], ico[5]]);
+        assert_eq!(count as usize, ICO_SIZES.len());
+    }
+
     #[test]
     fn check_target_deps() {
         let targets = get_targets();
@@ -0,0 +1,71 @@
+#!/usr/bin/env luajit
+-- Valid Lua 5.1 and LuaJIT code, including what luac 5.4 rejects or reads differently
+
+local a, b = 0x1F, 0xA.8p1
+local big, unsigned, imaginary = 42LL, 0xffULL, 12i
+local floats = { 3., .5, 3e10, 1E-2 }
+local s = "tab\t\x41\u{48}\z
+           continued \065"
+local long = [==[
+contains ]] and [[
+]==]
+
+--[[ long
+comment ]]
+--[==[ another ]==]
+
+local Player = {}
+Player.__index = Player
+
+function Player.new(x, y)
+    return setmetatable({ x = x, y = y, ["key"] = 1; 2, 3 }, Player)
+end
+
+function Player:move(dx, ...)
+    local args = { ... }
+    self.x = self.x + dx * #args ^ 2 ^ 0.5
+    return self
+end
+
+local function count(...)
+    return select("#", ...)
+end
+
+for i = 1, 10, 2 do
+    if i % 3 == 0 then
+        goto continue
+    elseif i > 8 then
+        break
+    else
+        print(i .. " " .. tostring(not true))
+    end
+
+    ::continue::
+end
+
+for k, v in pairs({ a = 1 }) do
+    while k ~= v do
+        k = v
+    end
+end
+
+repeat
+    local done = true
+until done
+
+do
+    local t = Player.new(1, 2):move(3, 4, 5)
+    print(t.x, count(1, nil, false), -t.y, (a or b) and s)
+end
+
+print "single string call"
+print { "table call" }
+local f = function() end
+
+goto finish
+do
+    print(long)
+end
+::finish::
+
+return big, unsigned, imaginary, floats, f
@@ -0,0 +1,443 @@
+use ansi_term::Color::{Green, Red};
+use std::{
+    fs,
+    io::{BufRead, BufReader},
+    path::Path,
+    process::{exit, ExitStatus},
+    thread,
+};
+
+use crate::{
+    actions::{self, CommandRunner},
+    config,
+    console::{exit_err, print_err, print_note, print_step, print_success, print_warn},
+    files, lua_deps,
+    project_config::ProjectConfig,
+    targets,
+};
+
+const MARKER: &str = "##lover-test";
+
+#[derive(PartialEq, Debug)]
+pub enum Status {
+    Pass,
+    Fail,
+    Error, // the test file couldn't be loaded
+}
+
+// Result printed by the test library (lua/test.lua)
+#[derive(Debug)]
+pub struct TestResult {
+    pub status: Status,
+    pub file: String,
+    pub location: String,
+    pub time: f64,
+    pub name: String,
+    pub message: String,
+}
+
+impl TestResult {
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.trim_end().split('\t');
+
+        if fields.next()? != MARKER {
+            return None;
+        }
+
+        let status = match fields.next()? {
+            "pass" => Status::Pass,
+            "fail" => Status::Fail,
+            "error" => Status::Error,
+            _ => return None,
+        };
+
+        Some(Self {
+            status,
+            file: unescape(fields.next()?),
+            location: unescape(fields.next()?),
+            time: fields.next()?.parse().unwrap_or(0.0),
+            name: unescape(fields.next()?),
+            message: unescape(fields.next().unwrap_or("")),
+        })
+    }
+
+    pub fn passed(&self) -> bool {
+        self.status == Status::Pass
+    }
+}
+
+pub enum Runner {
+    LuaJit,
+    Love,
+}
+
+impl Runner {
+    pub fn parse(name: &str) -> Self {
+        match name {
+            "luajit" => Self::LuaJit,
+            "love" => Self::Love,
+            _ => exit_err(format!(
+                "Unknown test runner '{}'. Use 'luajit' or 'love'.",
+                name
+            )),
+        }
+    }
+}
+
+fn unescape(text: &str) -> String {
+    let mut res = String::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => res.push('\n'),
+            Some('t') => res.push('\t'),
+            Some(other) => res.push(other),
+            None => res.push('\\'),
+        }
+    }
+
+    res
+}
+
+// `*_test.lua` files and Lua files inside `spec` directories
+pub fn is_test_file(relative: &Path) -> bool {
+    if relative.extension().is_none_or(|ext| ext != "lua") {
+        return false;
+    }
+
+    let name = relative.file_name().unwrap().to_str().unwrap_or("");
+
+    name.ends_with("_test.lua")
+        || relative
+            .parent()
+            .is_some_and(|parent| parent.components().any(|dir| dir.as_os_str() == "spec"))
+}
+
+// Test files relative to the project root. The build directory and hidden directories are skipped.
+pub fn find_tests(project: &ProjectConfig) -> Vec<String> {
+    let root = project.paths.get_root_dir();
+    let build = project.paths.get_build_dir();
+
+    let mut res: Vec<String> = files::get_file_tree(&root)
+        .iter()
+        .filter(|path| !path.starts_with(&build))
+        .map(|path| files::skip_path(path, &root))
+        .filter(|relative| {
+            !relative.components().any(|dir| {
+                dir.as_os_str()
+                    .to_str()
+                    .is_some_and(|dir| dir.starts_with('.'))
+            })
+        })
+        .filter(|relative| is_test_file(relative))
+        .map(|relative| relative.to_str().unwrap().replace('\\', "/"))
+        .collect();
+
+    res.sort();
+    res
+}
+
+fn lua_string(text: &str) -> String {
+    format!("[==[{}]==]", text)
+}
+
+// Script that runs the test files with the test library and Lover constants
+pub fn gen_harness(project: &ProjectConfig, test_files: &[String]) -> String {
+    let root = project.paths.get_root_dir();
+    let main = project.paths.get_main_dir();
+    let path_str = |path: &Path| {
+        let path = path.to_str().unwrap().replace('\\', "/");
+        path.trim_end_matches('/').to_string()
+    };
+
+    let mut res = targets::gen_env_code(project.get_env_map(actions::Context::Run));
+
+    res += &format!(
+        "local loverTest = {{\n    root = {},\n    main = {},\n    files = {{\n",
+        lua_string(&path_str(&root)),
+        lua_string(&path_str(&main))
+    );
+
+    for file in test_files {
+        res += &format!("        {},\n", lua_string(file));
+    }
+
+    res += "    },\n}\n";
+    res += include_str!("lua/test.lua");
+
+    targets::wrap_generated_code(res)
+}
+
+// LOVE without a window, so the tests can run on CI
+fn gen_headless_conf() -> &'static str {
+    "function love.conf(t)
+    t.window = false
+    t.modules.window = false
+    t.modules.graphics = false
+    t.modules.audio = false
+    t.modules.joystick = false
+end
+"
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "&#10;")
+}
+
+pub fn gen_junit(results: &[TestResult]) -> String {
+    let mut suites: Vec<(&str, Vec<&TestResult>)> = Vec::new();
+
+    for result in results {
+        match suites.iter_mut().find(|(file, _)| *file == result.file) {
+            Some((_, tests)) => tests.push(result),
+            None => suites.push((&result.file, vec![result])),
+        }
+    }
+
+    let failures = results.iter().filter(|res| !res.passed()).count();
+    let time: f64 = results.iter().map(|res| res.time).sum();
+
+    let mut res = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    res += &format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" time=\"{:.4}\">\n",
+        results.len(),
+        failures,
+        time
+    );
+
+    for (file, tests) in suites {
+        res += &format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.4}\">\n",
+            escape_xml(file),
+            tests.len(),
+            tests.iter().filter(|res| !res.passed()).count(),
+            tests.iter().map(|res| res.time).sum::<f64>()
+        );
+
+        for test in tests {
+            res += &format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.4}\"",
+                escape_xml(&test.name),
+                escape_xml(file),
+                test.time
+            );
+
+            if test.passed() {
+                res += "/>\n";
+                continue;
+            }
+
+            res += &format!(
+                ">\n      <failure message=\"{}\">{}: {}</failure>\n    </testcase>\n",
+                escape_xml(&test.message),
+                escape_xml(&test.location),
+                escape_xml(&test.message)
+            );
+        }
+
+        res += "  </testsuite>\n";
+    }
+
+    res += "</testsuites>\n";
+    res
+}
+
+fn print_result(result: &TestResult) {
+    match result.status {
+        Status::Pass => println!("  {} {}", Green.paint("PASS"), result.name),
+        Status::Fail => {
+            println!("  {} {}", Red.paint("FAIL"), result.name);
+            println!("       {}: {}", result.location, result.message);
+        }
+        Status::Error => {
+            println!("  {} {}", Red.paint("ERROR"), result.name);
+            println!("       {}: {}", result.location, result.message);
+        }
+    }
+}
+
+fn get_command(runner: &Runner, dir: &Path) -> CommandRunner {
+    let conf = config::get();
+
+    match runner {
+        Runner::LuaJit => {
+            let mut cmd = CommandRunner::new(&conf.software.luajit);
+            cmd.add_arg(dir.join("main.lua").to_str().unwrap());
+            cmd
+        }
+        Runner::Love => {
+            let mut cmd = conf.get_love_command();
+            cmd.add_arg(dir.to_str().unwrap());
+            cmd
+        }
+    }
+}
+
+// Runs the tests and returns their results with the exit status of the runner.
+// Output of the tests is printed as it is.
+pub fn run_tests(
+    project: &ProjectConfig,
+    test_files: &[String],
+    runner: &Runner,
+) -> (Vec<TestResult>, ExitStatus) {
+    let dir = project.paths.get_temp_dir().join("test");
+    files::create_dir(&dir);
+
+    let write = |name: &str, contents: &str| {
+        fs::write(dir.join(name), contents).unwrap_or_else(|err| {
+            exit_err(format!("Failed to write the test harness: {}", err));
+        });
+    };
+
+    write("main.lua", &gen_harness(project, test_files));
+    write("conf.lua", gen_headless_conf());
+
+    let mut cmd = get_command(runner, &dir);
+    lua_deps::prepare_run(project, &mut cmd);
+
+    let mut child = cmd.spawn_piped().unwrap_or_else(|err| exit_err(err));
+    let mut results: Vec<TestResult> = Vec::new();
+    let mut current_file: Option<String> = None;
+
+    thread::scope(|scope| {
+        if let Some(stderr) = child.stderr.take() {
+            scope.spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    eprintln!("{}", line);
+                }
+            });
+        }
+
+        let stdout = child.stdout.take().unwrap();
+
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let result = match TestResult::parse(&line) {
+                Some(result) => result,
+                None => {
+                    println!("{}", line);
+                    continue;
+                }
+            };
+
+            if current_file.as_ref() != Some(&result.file) {
+                print_step(&result.file);
+                current_file = Some(result.file.to_owned());
+            }
+
+            print_result(&result);
+            results.push(result);
+        }
+    });
+
+    let status = child
+        .wait()
+        .unwrap_or_else(|err| exit_err(format!("Failed to run the tests: {}", err)));
+
+    if results.is_empty() && !status.success() {
+        exit_err(format!("The test runner exited with {}", status));
+    }
+
+    (results, status)
+}
+
+pub fn test(
+    project: &ProjectConfig,
+    filters: &[String],
+    runner: Option<Runner>,
+    junit: Option<String>,
+) {
+    let test_files: Vec<String> = find_tests(project)
+        .into_iter()
+        .filter(|file| filters.is_empty() || filters.iter().any(|filter| file.contains(filter)))
+        .collect();
+
+    if test_files.is_empty() {
+        print_warn("No tests found. Test files are named `*_test.lua` or placed in `spec/`.");
+        return;
+    }
+
+    let runner = runner.unwrap_or_else(|| {
+        if CommandRunner::new(&config::get().software.luajit).exists() {
+            Runner::LuaJit
+        } else {
+            print_note("LuaJIT not found. The tests will run inside LOVE.");
+            Runner::Love
+        }
+    });
+
+    let (results, status) = run_tests(project, &test_files, &runner);
+    let failed = results.iter().filter(|res| !res.passed()).count();
+
+    if let Some(path) = junit {
+        fs::write(&path, gen_junit(&results)).unwrap_or_else(|err| {
+            exit_err(format!("Failed to write '{}': {}", path, err));
+        });
+
+        print_note(format!("JUnit report saved to '{}'", path));
+    }
+
+    println!();
+
+    if failed != 0 {
+        print_err(format!("{} of {} tests failed", failed, results.len()));
+        exit(1);
+    }
+
+    // The runner crashed, so the remaining tests didn't run
+    if !status.success() {
+        exit_err(format!(
+            "The test runner exited with {} after {} tests",
+            status,
+            results.len()
+        ));
+    }
+
+    print_success(format!("All {} tests passed", results.len()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files() {
+        assert!(is_test_file(Path::new("src/player_test.lua")));
+        assert!(is_test_file(Path::new("spec/player.lua")));
+        assert!(is_test_file(Path::new("spec/entities/enemy.lua")));
+        assert!(!is_test_file(Path::new("src/player.lua")));
+        assert!(!is_test_file(Path::new("spec/readme.md")));
+    }
+
+    #[test]
+    fn parse_results() {
+        let pass =
+            TestResult::parse("##lover-test\tpass\tspec/a.lua\tspec/a.lua:3\t0.0010\tadds\t")
+                .unwrap();
+        assert!(pass.passed());
+        assert_eq!(pass.name, "adds");
+
+        let fail = TestResult::parse(
+            "##lover-test\tfail\tspec/a.lua\tsrc/math.lua:12\t0.0000\tdivides\texpected 2,\\n got \\\\1",
+        )
+        .unwrap();
+
+        assert_eq!(fail.status, Status::Fail);
+        assert_eq!(fail.location, "src/math.lua:12");
+        assert_eq!(fail.message, "expected 2,\n got \\1");
+        assert!(TestResult::parse("hello from the game").is_none());
+
+        let junit = gen_junit(&[pass, fail]);
+        assert!(junit.contains("<testsuites tests=\"2\" failures=\"1\""));
+        assert!(junit.contains("<failure message=\"expected 2,&#10; got \\1\">src/math.lua:12"));
+    }
+}
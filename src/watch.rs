@@ -0,0 +1,190 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Child,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    actions::{self, CommandRunner},
+    console::{exit_err, print_err, print_note, print_step, print_warn},
+    files,
+    hot::HotServer,
+    project_config::Paths,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+// Modification times and sizes of watched files
+pub struct Snapshot {
+    files: HashMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+impl Snapshot {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let mut files = HashMap::new();
+
+        for path in paths {
+            if let Ok(meta) = fs::metadata(&path) {
+                files.insert(path, (meta.modified().ok(), meta.len()));
+            }
+        }
+
+        Self { files }
+    }
+
+    // Files that were added, removed or modified since `self`
+    pub fn get_changed(&self, recent: &Self) -> Vec<PathBuf> {
+        let mut res: Vec<PathBuf> = Vec::new();
+
+        for (path, state) in &self.files {
+            if recent.files.get(path) != Some(state) {
+                res.push(path.to_owned());
+            }
+        }
+
+        for path in recent.files.keys() {
+            if !self.files.contains_key(path) {
+                res.push(path.to_owned());
+            }
+        }
+
+        res.sort();
+        res
+    }
+}
+
+// Files to watch. Excluded and ignored files of a project are skipped.
+pub fn get_watched_files(main: &Path, paths: Option<&Paths>) -> Vec<PathBuf> {
+    match paths {
+        Some(paths) => paths.get_files(),
+        None => files::get_file_tree(main),
+    }
+}
+
+fn start(cmd: &CommandRunner) -> Option<Child> {
+    match cmd.spawn() {
+        Ok(child) => Some(child),
+        Err(err) => {
+            print_err(err);
+            None
+        }
+    }
+}
+
+fn stop(child: &mut Option<Child>) {
+    if let Some(mut process) = child.take() {
+        let _ = process.kill();
+        let _ = process.wait();
+    }
+}
+
+// Runs the game and restarts it whenever a watched file changes.
+// Scripts are parsed first and the game keeps running if any of them is invalid.
+// With `hot`, Lua modules are reloaded without a restart. `prepare` is called before each start.
+pub fn run(
+    cmd: &CommandRunner,
+    main: &Path,
+    paths: Option<&Paths>,
+    mut hot: Option<&mut HotServer>,
+    prepare: impl Fn(),
+) {
+    let mut snapshot = Snapshot::new(get_watched_files(main, paths));
+
+    prepare();
+    let mut child = start(cmd);
+
+    if child.is_none() {
+        exit_err("Failed to start the game.");
+    }
+
+    print_note("Watching for changes. Press Ctrl+C to stop.");
+
+    let mut exited = false;
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        if let Some(server) = hot.as_deref_mut() {
+            server.accept_clients();
+        }
+
+        if let Some(process) = &mut child {
+            if !exited && matches!(process.try_wait(), Ok(Some(_))) {
+                exited = true;
+                print_note("The game was closed. It will start again when a file changes.");
+            }
+        }
+
+        let recent = Snapshot::new(get_watched_files(main, paths));
+        let changed = snapshot.get_changed(&recent);
+
+        if changed.is_empty() {
+            continue;
+        }
+
+        snapshot = recent;
+
+        println!();
+        print_step(format!(
+            "Changed: {}",
+            changed
+                .iter()
+                .map(|path| files::skip_path_string(path, main))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+
+        if !actions::try_parse_all(main) {
+            print_warn("Fix the errors above to restart the game.");
+            continue;
+        }
+
+        if let Some(server) = hot.as_deref_mut() {
+            if !exited && server.reload(main, &changed) {
+                continue;
+            }
+        }
+
+        stop(&mut child);
+        prepare();
+        child = start(cmd);
+        exited = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_changes() {
+        let dir = std::env::temp_dir().join("lover_test_watch");
+        let _ = fs::remove_dir_all(&dir);
+        files::create_dir(&dir);
+
+        let main = dir.join("main.lua");
+        let conf = dir.join("conf.lua");
+
+        fs::write(&main, "print('a')").unwrap();
+        fs::write(&conf, "").unwrap();
+
+        let before = Snapshot::new(files::get_file_tree(&dir));
+
+        fs::write(&main, "print('ab')").unwrap();
+        fs::remove_file(&conf).unwrap();
+        fs::write(dir.join("player.lua"), "").unwrap();
+
+        let after = Snapshot::new(files::get_file_tree(&dir));
+        let changed = before.get_changed(&after);
+
+        assert_eq!(changed, vec![conf, main.to_owned(), dir.join("player.lua")]);
+        assert!(after
+            .get_changed(&Snapshot::new(vec![main, dir.join("player.lua")]))
+            .is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}